@@ -0,0 +1,11 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2DArray atlas;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = texture(atlas, vec3(fragUv, 0.0));
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+layout(location = 0) in vec2 inPos;
+layout(location = 1) in vec2 inUv;
+// per instance, minimum and maximum corners
+layout(location = 2) in vec4 inRect;
+layout(location = 3) in vec4 inUvRect;
+
+layout(location = 0) out vec2 fragUv;
+
+void main() {
+    vec2 pos = mix(inRect.xy, inRect.zw, inPos + 0.5);
+    gl_Position = ubo.proj * vec4(pos, 0.0, 1.0);
+    fragUv = mix(inUvRect.xy, inUvRect.zw, inUv);
+}
@@ -0,0 +1,16 @@
+#version 450
+
+layout(push_constant) uniform OverlayPushConstants {
+    vec4 rect;
+    vec4 fill;
+    vec4 border;
+    vec2 viewport;
+    float borderWidth;
+} pc;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec2 inset = min(gl_FragCoord.xy - pc.rect.xy, pc.rect.zw - gl_FragCoord.xy);
+    outColor = min(inset.x, inset.y) < pc.borderWidth ? pc.border : pc.fill;
+}
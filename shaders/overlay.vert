@@ -0,0 +1,16 @@
+#version 450
+
+layout(push_constant) uniform OverlayPushConstants {
+    vec4 rect;
+    vec4 fill;
+    vec4 border;
+    vec2 viewport;
+    float borderWidth;
+} pc;
+
+void main() {
+    // triangle strip over the corners, row by row
+    vec2 corner = vec2(gl_VertexIndex & 1, gl_VertexIndex >> 1);
+    vec2 pos = mix(pc.rect.xy, pc.rect.zw, corner);
+    gl_Position = vec4(pos / pc.viewport * 2.0 - 1.0, 0.0, 1.0);
+}
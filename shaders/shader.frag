@@ -1,8 +1,123 @@
 #version 450
 
+// one layer per animation frame
+layout(set = 1, binding = 0) uniform sampler2DArray texSampler;
+layout(set = 1, binding = 1) uniform sampler2DArray nearestSampler;
+
+layout(push_constant) uniform PushConstants {
+    int scaleFilter;
+    uint pixelArt;
+    uint pixelGrid;
+    int background;
+    float checkerSize;
+    uint transform;
+    float opacity;
+    float offset;
+    uint layer;
+} pc;
+
 layout(location = 0) out vec4 outColor;
-layout(location = 0) in vec3 fragColor;
+layout(location = 0) in vec2 fragUv;
+
+// matches ScaleFilter
+const int FILTER_TRILINEAR = 0;
+const int FILTER_BICUBIC = 1;
+const int FILTER_LANCZOS = 2;
+
+// matches Background
+const int BACKGROUND_CHECKERBOARD = 0;
+
+const float PI = 3.14159265358979;
+
+const vec3 GRID_COLOR = vec3(0.5);
+const float GRID_OPACITY = 0.5;
+
+// linear, roughly 80% and 60% gray in sRGB
+const vec3 CHECKER_LIGHT = vec3(0.6);
+const vec3 CHECKER_DARK = vec3(0.32);
+
+float sinc(float x) {
+    if (abs(x) < 1e-5) {
+        return 1.0;
+    }
+    return sin(PI * x) / (PI * x);
+}
+
+float lanczos2(float x) {
+    return abs(x) < 2.0 ? sinc(x) * sinc(x / 2.0) : 0.0;
+}
+
+// Mitchell-Netravali with B = C = 1/3
+float mitchell(float x) {
+    x = abs(x);
+    if (x < 1.0) {
+        return (7.0 * x * x * x - 12.0 * x * x + 16.0 / 3.0) / 6.0;
+    }
+    if (x < 2.0) {
+        return (-7.0 / 3.0 * x * x * x + 12.0 * x * x - 20.0 * x + 32.0 / 3.0) / 6.0;
+    }
+    return 0.0;
+}
+
+float kernel(float x) {
+    return pc.scaleFilter == FILTER_LANCZOS ? lanczos2(x) : mitchell(x);
+}
+
+// Resamples the mip level just above the screen's resolution with a kernel
+// stretched over the rest of the footprint, so minification keeps detail that
+// trilinear filtering blurs away.
+vec4 resample(vec2 uv, float lod) {
+    int level = int(floor(lod));
+    float scale = exp2(lod - float(level));
+    ivec2 size = textureSize(texSampler, level).xy;
+    vec2 pos = uv * vec2(size) - 0.5;
+    ivec2 center = ivec2(floor(pos));
+    int radius = int(ceil(2.0 * scale));
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int y = 1 - radius; y <= radius; y++) {
+        for (int x = 1 - radius; x <= radius; x++) {
+            ivec2 texel = center + ivec2(x, y);
+            vec2 offset = (vec2(texel) - pos) / scale;
+            float weight = kernel(offset.x) * kernel(offset.y);
+            ivec3 clamped = ivec3(clamp(texel, ivec2(0), size - 1), pc.layer);
+            sum += weight * texelFetch(texSampler, clamped, level);
+            total += weight;
+        }
+    }
+
+    return clamp(sum / total, 0.0, 1.0);
+}
 
 void main() {
-    outColor = vec4(fragColor, 1.0);
+    float maxLod = float(textureQueryLevels(texSampler) - 1);
+    float lod = clamp(textureQueryLod(texSampler, fragUv).y, 0.0, maxLod);
+
+    vec3 uvw = vec3(fragUv, pc.layer);
+    if (pc.pixelArt != 0) {
+        outColor = texture(nearestSampler, uvw);
+    } else if (pc.scaleFilter == FILTER_TRILINEAR) {
+        outColor = texture(texSampler, uvw);
+    } else {
+        outColor = resample(fragUv, lod);
+    }
+
+    // colors are premultiplied, so compositing over is a single mad
+    if (pc.background == BACKGROUND_CHECKERBOARD) {
+        ivec2 cell = ivec2(floor(gl_FragCoord.xy / pc.checkerSize));
+        vec3 checker = ((cell.x + cell.y) & 1) == 0 ? CHECKER_LIGHT : CHECKER_DARK;
+        outColor = vec4(outColor.rgb + checker * (1.0 - outColor.a), 1.0);
+    }
+
+    // one window pixel wide lines along the left and top edge of each texel
+    if (pc.pixelGrid != 0) {
+        vec2 texel = fragUv * vec2(textureSize(nearestSampler, 0).xy);
+        vec2 edge = step(fract(texel), fwidth(texel));
+        float line = max(edge.x, edge.y);
+        outColor = mix(outColor, vec4(GRID_COLOR, 1.0), line * GRID_OPACITY);
+    }
+
+    // fades in or out during a transition
+    outColor *= pc.opacity;
 }
@@ -1,17 +1,37 @@
 #version 450
 
-layout(binding = 0) uniform UniformBufferObject {
+struct Transform {
     mat4 model;
     mat4 view;
     mat4 proj;
+};
+
+// matches MAX_IMAGE_TRANSFORMS
+layout(binding = 0) uniform UniformBufferObject {
+    Transform transforms[4];
 } ubo;
 
+layout(push_constant) uniform PushConstants {
+    int scaleFilter;
+    uint pixelArt;
+    uint pixelGrid;
+    int background;
+    float checkerSize;
+    uint transform;
+    float opacity;
+    float offset;
+    uint layer;
+} pc;
+
 layout(location = 0) in vec2 inPos;
-layout(location = 1) in vec3 inColor;
+layout(location = 1) in vec2 inUv;
 
-layout(location = 0) out vec3 fragColor;
+layout(location = 0) out vec2 fragUv;
 
 void main() {
-    gl_Position = ubo.proj * ubo.view * ubo.model * vec4(inPos, 0.0, 1.0);
-    fragColor = inColor;
+    Transform transform = ubo.transforms[pc.transform];
+    gl_Position = transform.proj * transform.view * transform.model * vec4(inPos, 0.0, 1.0);
+    // the window spans 2 in clip space
+    gl_Position.x += 2.0 * pc.offset * gl_Position.w;
+    fragUv = inUv;
 }
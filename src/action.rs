@@ -0,0 +1,60 @@
+use serde::Deserialize;
+
+/// Something the user can ask for through a key, mouse button or the wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Next,
+    Prev,
+    NextPage,
+    PrevPage,
+    First,
+    Last,
+    /// Zooms around the window center, or the cursor when bound to the wheel.
+    ZoomIn,
+    ZoomOut,
+    /// Re-applies the current fit mode.
+    ResetZoom,
+    Fit,
+    Fill,
+    ActualSize,
+    FitWidth,
+    FitHeight,
+    /// Drags the image while a mouse button is held. Does nothing on keys.
+    Pan,
+    RotateCw,
+    RotateCcw,
+    FlipHorizontal,
+    FlipVertical,
+    CycleScaleFilter,
+    TogglePixelArt,
+    TogglePixelGrid,
+    CycleBackground,
+    SmallerCheckers,
+    LargerCheckers,
+    ToggleFitWindow,
+    ToggleFullscreen,
+    ExitFullscreen,
+    MemoryStats,
+    /// Pauses or resumes an animation.
+    TogglePlayback,
+    /// Pauses an animation and steps a frame forward or back.
+    NextFrame,
+    PrevFrame,
+    FasterPlayback,
+    SlowerPlayback,
+    /// Starts, pauses or resumes the slideshow.
+    ToggleSlideshow,
+    /// Compares the current image with the ones after it, or stops.
+    ToggleCompare,
+    /// Switches comparing between side by side and swiping.
+    CycleCompareMode,
+    /// Switches between the image and a grid of thumbnails of the playlist.
+    ToggleGallery,
+    /// Opens the image selected in the gallery.
+    Open,
+    /// Moves the gallery selection a row up or down. Does nothing otherwise.
+    PrevRow,
+    NextRow,
+    Quit,
+}
@@ -0,0 +1,448 @@
+use crate::{buffer::Buffer, constants::ALLOCATOR_BLOCK_SIZE};
+use ash::vk;
+use std::{collections::HashMap, error::Error, ffi::c_void, fmt};
+
+/// Blocks are kept apart by tiling as well as memory type, so buffers and
+/// optimal-tiling images never have to respect `bufferImageGranularity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PoolKey {
+    memory_type_index: u32,
+    linear: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FreeRange {
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+}
+
+struct Block {
+    id: u64,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    /// Sorted by offset, never adjacent to each other.
+    free_ranges: Vec<FreeRange>,
+    allocation_count: usize,
+    /// Whole-block mapping for host visible memory.
+    ptr: Option<*mut c_void>,
+    /// Holds a single oversized allocation and is released along with it.
+    dedicated: bool,
+}
+
+#[derive(Debug)]
+pub struct Allocation {
+    memory: vk::DeviceMemory,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    pool: PoolKey,
+    block_id: u64,
+    ptr: Option<*mut c_void>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllocatorStats {
+    pub block_count: usize,
+    pub allocation_count: usize,
+    /// Bytes obtained from `vkAllocateMemory`.
+    pub reserved_bytes: vk::DeviceSize,
+    /// Bytes handed out to buffers and images.
+    pub used_bytes: vk::DeviceSize,
+}
+
+/// Device local memory as reported by `VK_EXT_memory_budget`, which also
+/// accounts for other processes sharing the GPU.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryBudget {
+    pub budget: vk::DeviceSize,
+    pub usage: vk::DeviceSize,
+}
+
+/// Sub-allocates buffers and images from a few large `VkDeviceMemory` blocks
+/// per memory type. Freed ranges are coalesced and reused in place.
+pub struct Allocator {
+    device_mem_props: vk::PhysicalDeviceMemoryProperties,
+    pools: HashMap<PoolKey, Vec<Block>>,
+    next_block_id: u64,
+}
+
+impl Allocator {
+    pub fn new(device_mem_props: vk::PhysicalDeviceMemoryProperties) -> Self {
+        Self {
+            device_mem_props,
+            pools: HashMap::new(),
+            next_block_id: 0,
+        }
+    }
+
+    pub fn allocate(
+        &mut self,
+        device: &ash::Device,
+        requirements: vk::MemoryRequirements,
+        mem_props: vk::MemoryPropertyFlags,
+        linear: bool,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> Result<Allocation, Box<dyn Error>> {
+        let memory_type_index: u32 = Buffer::find_memory_type_index(
+            self.device_mem_props,
+            requirements.memory_type_bits,
+            mem_props,
+        )?
+        .try_into()?;
+        let pool = PoolKey {
+            memory_type_index,
+            linear,
+        };
+
+        let blocks = self.pools.entry(pool).or_default();
+        for block in blocks.iter_mut().filter(|x| !x.dedicated) {
+            if let Some(offset) = block.carve(requirements.size, requirements.alignment) {
+                return Ok(block.allocation(pool, offset, requirements.size));
+            }
+        }
+
+        let dedicated = requirements.size > ALLOCATOR_BLOCK_SIZE / 2;
+        let block_size = if dedicated {
+            requirements.size
+        } else {
+            ALLOCATOR_BLOCK_SIZE
+        };
+
+        let alloc_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(block_size)
+            .memory_type_index(memory_type_index);
+        let memory = unsafe { device.allocate_memory(&alloc_info, allocation_callbacks)? };
+
+        let host_visible = self.device_mem_props.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        let ptr = if host_visible {
+            match unsafe {
+                device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+            } {
+                Ok(ptr) => Some(ptr),
+                Err(e) => {
+                    unsafe { device.free_memory(memory, allocation_callbacks) };
+                    return Err(e.into());
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut block = Block {
+            id: self.next_block_id,
+            memory,
+            size: block_size,
+            free_ranges: vec![FreeRange {
+                offset: 0,
+                size: block_size,
+            }],
+            allocation_count: 0,
+            ptr,
+            dedicated,
+        };
+        self.next_block_id += 1;
+
+        let offset = block
+            .carve(requirements.size, requirements.alignment)
+            .unwrap();
+        let allocation = block.allocation(pool, offset, requirements.size);
+        blocks.push(block);
+
+        Ok(allocation)
+    }
+
+    pub fn free(
+        &mut self,
+        device: &ash::Device,
+        allocation: Allocation,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        let blocks = self.pools.get_mut(&allocation.pool).unwrap();
+        let index = blocks
+            .iter()
+            .position(|x| x.id == allocation.block_id)
+            .unwrap();
+
+        let block = &mut blocks[index];
+        block.release(allocation.offset, allocation.size);
+
+        // keep one empty block around so alternating alloc/free doesn't thrash
+        let spare_blocks = blocks
+            .iter()
+            .filter(|x| x.allocation_count == 0 && !x.dedicated)
+            .count();
+        let block = &blocks[index];
+        if block.allocation_count == 0 && (block.dedicated || spare_blocks > 1) {
+            blocks.remove(index).cleanup(device, allocation_callbacks);
+        }
+    }
+
+    pub fn stats(&self) -> AllocatorStats {
+        let mut stats = AllocatorStats::default();
+        for block in self.pools.values().flatten() {
+            stats.block_count += 1;
+            stats.allocation_count += block.allocation_count;
+            stats.reserved_bytes += block.size;
+            stats.used_bytes += block.size - block.free_ranges.iter().map(|x| x.size).sum::<u64>();
+        }
+        stats
+    }
+
+    /// Bytes free inside blocks of memory types with `mem_props`, which new
+    /// allocations fill before asking the driver for more.
+    pub fn free_bytes(&self, mem_props: vk::MemoryPropertyFlags) -> vk::DeviceSize {
+        self.pools
+            .iter()
+            .filter(|(pool, _)| {
+                self.device_mem_props.memory_types[pool.memory_type_index as usize]
+                    .property_flags
+                    .contains(mem_props)
+            })
+            .flat_map(|(_, blocks)| blocks)
+            .flat_map(|x| &x.free_ranges)
+            .map(|x| x.size)
+            .sum()
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        for block in self.pools.into_values().flatten() {
+            block.cleanup(device, allocation_callbacks);
+        }
+    }
+}
+
+impl Block {
+    /// Takes `size` bytes aligned to `alignment` from the first range that fits.
+    fn carve(&mut self, size: vk::DeviceSize, alignment: vk::DeviceSize) -> Option<vk::DeviceSize> {
+        let (index, offset) = self.free_ranges.iter().enumerate().find_map(|(i, range)| {
+            let offset = range.offset.next_multiple_of(alignment.max(1));
+            (offset + size <= range.offset + range.size).then_some((i, offset))
+        })?;
+
+        let range = self.free_ranges.remove(index);
+        let tail = FreeRange {
+            offset: offset + size,
+            size: range.offset + range.size - (offset + size),
+        };
+        if tail.size > 0 {
+            self.free_ranges.insert(index, tail);
+        }
+        let head = FreeRange {
+            offset: range.offset,
+            size: offset - range.offset,
+        };
+        if head.size > 0 {
+            self.free_ranges.insert(index, head);
+        }
+
+        self.allocation_count += 1;
+        Some(offset)
+    }
+
+    /// Returns a range to the free list, merging it with its neighbors.
+    fn release(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let index = self.free_ranges.partition_point(|x| x.offset < offset);
+        self.free_ranges.insert(index, FreeRange { offset, size });
+
+        if index + 1 < self.free_ranges.len() {
+            let next = self.free_ranges[index + 1];
+            if offset + size == next.offset {
+                self.free_ranges[index].size += next.size;
+                self.free_ranges.remove(index + 1);
+            }
+        }
+        if index > 0 {
+            let prev = self.free_ranges[index - 1];
+            if prev.offset + prev.size == offset {
+                self.free_ranges[index - 1].size += self.free_ranges[index].size;
+                self.free_ranges.remove(index);
+            }
+        }
+
+        self.allocation_count -= 1;
+    }
+
+    fn allocation(
+        &self,
+        pool: PoolKey,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> Allocation {
+        Allocation {
+            memory: self.memory,
+            offset,
+            size,
+            pool,
+            block_id: self.id,
+            ptr: self
+                .ptr
+                .map(|x| unsafe { x.add(offset.try_into().unwrap()) }),
+        }
+    }
+
+    fn cleanup(self, device: &ash::Device, allocation_callbacks: Option<&vk::AllocationCallbacks>) {
+        unsafe {
+            if self.ptr.is_some() {
+                device.unmap_memory(self.memory);
+            }
+            device.free_memory(self.memory, allocation_callbacks);
+        }
+    }
+}
+
+impl Allocation {
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Host pointer to the start of the allocation, for host visible memory.
+    pub fn ptr(&self) -> Option<*mut c_void> {
+        self.ptr
+    }
+}
+
+impl MemoryBudget {
+    pub fn available(&self) -> vk::DeviceSize {
+        self.budget.saturating_sub(self.usage)
+    }
+}
+
+impl fmt::Display for AllocatorStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mib = |x: vk::DeviceSize| x as f64 / (1024.0 * 1024.0);
+        write!(
+            f,
+            "{} allocations in {} blocks, {:.1} MiB used of {:.1} MiB reserved",
+            self.allocation_count,
+            self.block_count,
+            mib(self.used_bytes),
+            mib(self.reserved_bytes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(size: vk::DeviceSize) -> Block {
+        Block {
+            id: 0,
+            memory: vk::DeviceMemory::null(),
+            size,
+            free_ranges: vec![FreeRange { offset: 0, size }],
+            allocation_count: 0,
+            ptr: None,
+            dedicated: false,
+        }
+    }
+
+    fn free_ranges(block: &Block) -> Vec<(vk::DeviceSize, vk::DeviceSize)> {
+        block
+            .free_ranges
+            .iter()
+            .map(|x| (x.offset, x.size))
+            .collect()
+    }
+
+    #[test]
+    fn carve_leaves_the_tail_free() {
+        let mut block = block(1024);
+
+        assert_eq!(block.carve(100, 1), Some(0));
+        assert_eq!(free_ranges(&block), [(100, 924)]);
+        assert_eq!(block.allocation_count, 1);
+    }
+
+    #[test]
+    fn carve_leaves_the_alignment_padding_free() {
+        let mut block = block(1024);
+        block.carve(100, 1).unwrap();
+
+        assert_eq!(block.carve(10, 256), Some(256));
+        assert_eq!(free_ranges(&block), [(100, 156), (266, 758)]);
+    }
+
+    #[test]
+    fn carve_takes_a_whole_range_that_fits_exactly() {
+        let mut block = block(1024);
+
+        assert_eq!(block.carve(1024, 256), Some(0));
+        assert_eq!(free_ranges(&block), []);
+        assert_eq!(block.carve(1, 1), None);
+    }
+
+    #[test]
+    fn carve_skips_ranges_too_small_once_aligned() {
+        let mut block = block(1024);
+        block.carve(1, 1).unwrap();
+
+        assert_eq!(block.carve(1000, 64), None);
+        assert_eq!(block.carve(960, 64), Some(64));
+        assert_eq!(free_ranges(&block), [(1, 63)]);
+    }
+
+    #[test]
+    fn release_merges_with_the_next_range() {
+        let mut block = block(1024);
+        block.carve(100, 1).unwrap();
+        let b = block.carve(100, 1).unwrap();
+        block.release(b, 100);
+
+        assert_eq!(free_ranges(&block), [(100, 924)]);
+    }
+
+    #[test]
+    fn release_merges_with_the_previous_range() {
+        let mut block = block(300);
+        let a = block.carve(100, 1).unwrap();
+        let b = block.carve(100, 1).unwrap();
+        block.carve(100, 1).unwrap();
+        block.release(a, 100);
+        block.release(b, 100);
+
+        assert_eq!(free_ranges(&block), [(0, 200)]);
+    }
+
+    #[test]
+    fn release_merges_with_both_neighbors() {
+        let mut block = block(300);
+        let a = block.carve(100, 1).unwrap();
+        let b = block.carve(100, 1).unwrap();
+        let c = block.carve(100, 1).unwrap();
+        block.release(a, 100);
+        block.release(c, 100);
+
+        assert_eq!(free_ranges(&block), [(0, 100), (200, 100)]);
+        block.release(b, 100);
+        assert_eq!(free_ranges(&block), [(0, 300)]);
+    }
+
+    #[test]
+    fn releasing_everything_frees_the_whole_block() {
+        let mut block = block(1024);
+        let allocations: Vec<_> = [(100, 1), (10, 256), (300, 64), (1, 1)]
+            .into_iter()
+            .map(|(size, alignment)| (block.carve(size, alignment).unwrap(), size))
+            .collect();
+        for (offset, size) in allocations.into_iter().rev() {
+            block.release(offset, size);
+        }
+
+        assert_eq!(free_ranges(&block), [(0, block.size)]);
+        assert_eq!(block.allocation_count, 0);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::constants::{
+    DEFAULT_FRAME_DELAY, MAX_PLAYBACK_SPEED, MIN_FRAME_DELAY, MIN_PLAYBACK_SPEED,
+};
+use image::{
+    AnimationDecoder, ImageFormat, RgbaImage,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    metadata::LoopCount,
+};
+use std::{
+    io::{BufRead, Seek},
+    time::{Duration, Instant},
+};
+
+/// Where an animation is and how it plays. Frames advance on their own
+/// schedule, measured from when the current one went up.
+#[derive(Debug)]
+pub struct Playback {
+    frame: usize,
+    frame_shown_at: Instant,
+    paused: bool,
+    speed: f32,
+    /// Times the animation went past its last frame.
+    loops: u32,
+}
+
+/// Frames of an animation with how long each stays up.
+pub struct DecodedFrames {
+    pub frames: Vec<(RgbaImage, Duration)>,
+    /// Times the file asks the animation to play, or 0 for forever.
+    pub loop_count: u32,
+}
+
+/// Frames of a GIF, APNG or animated WebP, as many as fit in `max_bytes` but
+/// at least the first. The decoder composites each onto the full canvas,
+/// applying the disposal and blend mode of the frames before it. Returns
+/// `None` for other formats and for still PNG and WebP images, which decode
+/// the usual way.
+pub fn decode_frames(
+    reader: impl BufRead + Seek,
+    format: ImageFormat,
+    max_bytes: u64,
+) -> image::ImageResult<Option<DecodedFrames>> {
+    let (loop_count, frames) = match format {
+        ImageFormat::Gif => {
+            let decoder = GifDecoder::new(reader)?;
+            (decoder.loop_count(), decoder.into_frames())
+        }
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            let decoder = decoder.apng()?;
+            (decoder.loop_count(), decoder.into_frames())
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            (decoder.loop_count(), decoder.into_frames())
+        }
+        _ => return Ok(None),
+    };
+
+    let mut decoded = Vec::new();
+    let mut size = 0;
+    for frame in frames {
+        let frame = frame?;
+        // the rest couldn't be cached anyway, so long animations stop short
+        let frame_size = frame.buffer().as_raw().len() as u64;
+        if !decoded.is_empty() && size + frame_size > max_bytes {
+            break;
+        }
+        size += frame_size;
+
+        // browsers stretch the tiny delays old GIFs use to mean "fast"
+        let delay = Duration::from(frame.delay());
+        let delay = if delay <= MIN_FRAME_DELAY {
+            DEFAULT_FRAME_DELAY
+        } else {
+            delay
+        };
+        decoded.push((frame.into_buffer(), delay));
+    }
+
+    Ok(Some(DecodedFrames {
+        frames: decoded,
+        loop_count: match loop_count {
+            LoopCount::Infinite => 0,
+            LoopCount::Finite(count) => count.get(),
+        },
+    }))
+}
+
+impl Playback {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            frame_shown_at: Instant::now(),
+            paused: false,
+            speed: 1.0,
+            loops: 0,
+        }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Starts over from the first frame, keeping the speed and whether it is
+    /// paused.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.frame_shown_at = Instant::now();
+        self.loops = 0;
+    }
+
+    /// Pauses or resumes. Resuming after the last loop plays it all again.
+    pub fn toggle_pause(&mut self, loop_count: u32) {
+        self.paused = !self.paused;
+        if !self.paused && self.is_finished(loop_count) {
+            self.restart();
+        }
+        self.frame_shown_at = Instant::now();
+    }
+
+    /// Pauses and moves `offset` frames, wrapping around at either end.
+    pub fn step(&mut self, offset: isize, frame_count: usize) {
+        self.paused = true;
+        let len = frame_count as isize;
+        self.frame = (self.frame as isize + offset).rem_euclid(len) as usize;
+    }
+
+    /// Multiplies the speed by `factor`, within the supported range.
+    pub fn change_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+    }
+
+    /// Keeps the frame within an animation that may have fewer frames now.
+    pub fn clamp(&mut self, frame_count: usize) {
+        self.frame = self.frame.min(frame_count.saturating_sub(1));
+    }
+
+    /// Moves past every frame whose time is up, stopping on the last frame
+    /// once the animation has played `loop_count` times, or never when it is
+    /// zero. Returns whether the frame changed.
+    pub fn advance(&mut self, delays: &[Duration], loop_count: u32) -> bool {
+        if delays.len() < 2 || self.paused {
+            return false;
+        }
+
+        let before = self.frame;
+        let now = Instant::now();
+        // after a stall, carry on from now rather than race through the backlog
+        let cycle = delays.iter().sum::<Duration>().div_f32(self.speed);
+        if now.saturating_duration_since(self.frame_shown_at) > cycle {
+            self.frame_shown_at = now - self.delay(delays);
+        }
+        while !self.is_finished(loop_count) {
+            let shown_until = self.frame_shown_at + self.delay(delays);
+            if shown_until > now {
+                break;
+            }
+            self.frame_shown_at = shown_until;
+            self.frame += 1;
+            if self.frame == delays.len() {
+                self.frame = 0;
+                self.loops += 1;
+            }
+        }
+        // the last loop ends on the last frame rather than wrapping
+        if self.is_finished(loop_count) {
+            self.frame = delays.len() - 1;
+        }
+        self.frame != before
+    }
+
+    /// When the current frame is due to be replaced, if it will be.
+    pub fn next_frame_at(&self, delays: &[Duration], loop_count: u32) -> Option<Instant> {
+        let playing = delays.len() > 1 && !self.paused;
+        (playing && !self.is_finished(loop_count)).then(|| self.frame_shown_at + self.delay(delays))
+    }
+
+    fn delay(&self, delays: &[Duration]) -> Duration {
+        delays[self.frame].div_f32(self.speed)
+    }
+
+    fn is_finished(&self, loop_count: u32) -> bool {
+        loop_count > 0 && self.loops >= loop_count
+    }
+}
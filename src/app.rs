@@ -1,22 +1,203 @@
-use crate::{constants::*, engine::Engine, uniform_buffer_object::UniformBufferObject};
+use crate::{
+    action::Action,
+    animation::Playback,
+    background::Background,
+    bindings::{Bindings, KeyChord, WheelDirection},
+    camera::Camera,
+    color,
+    compare::{Compare, CompareMode},
+    constants::*,
+    decoder::{DecodedImage, Decoder},
+    engine::Engine,
+    fit_mode::FitMode,
+    gallery::Gallery,
+    image_cache::LruCache,
+    orientation::Orientation,
+    pipeline::Pipeline,
+    playlist::Playlist,
+    push_constants::{OverlayPushConstants, PushConstants},
+    settings::Settings,
+    slideshow::Slideshow,
+    texture_upload::{TextureUpload, ThumbnailUpload},
+    thumbnail_atlas::ThumbnailAtlas,
+    tiled_texture::TiledTexture,
+    transition::{Layer, Transition},
+    uniform_buffer_object::{Transform, UniformBufferObject},
+    user_event::UserEvent,
+    vertex::ThumbnailInstance,
+    watcher::{FileChange, Watcher},
+    window_geometry::WindowGeometry,
+};
 use ash::vk;
-use core::f32;
-use glam::{Mat4, vec3};
+use glam::{Mat4, Vec2, vec2, vec3, vec4};
+use image::imageops::FilterType;
+use std::{
+    collections::HashSet,
+    error::Error,
+    mem,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 use winit::{
-    application::ApplicationHandler, event::WindowEvent, event_loop::ActiveEventLoop,
-    window::WindowId,
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy},
+    keyboard::ModifiersState,
+    window::{Fullscreen, Window, WindowAttributes, WindowId},
 };
 
 pub struct App {
     engine: Engine,
-    start_time: std::time::SystemTime,
+    settings: Settings,
+    bindings: Bindings,
+    playlist: Playlist,
+    decoder: Decoder,
+    pixel_cache: LruCache<PathBuf, DecodedImage>,
+    texture_cache: LruCache<PathBuf, CachedTexture>,
+    uploads: Vec<PendingUpload>,
+    /// Image whose texture is drawn, which lags behind the playlist while decoding.
+    shown: Option<PathBuf>,
+    /// How the shown image is laid out, starting from its EXIF orientation.
+    orientation: Orientation,
+    shown_at: Instant,
+    /// Frame of the shown image when it is animated.
+    playback: Playback,
+    slideshow: Slideshow,
+    /// Image being replaced while a slideshow transition plays.
+    outgoing: Option<Outgoing>,
+    /// Images shown along with the current one while comparing.
+    compare: Option<Compare>,
+    camera: Camera,
+    cursor_position: Vec2,
+    dragging: bool,
+    /// The swipe divider follows the cursor.
+    dragging_divider: bool,
+    modifiers: ModifiersState,
+    /// Wheel movement not yet turned into whole-line actions.
+    scroll_lines: Vec2,
+    /// Last time the cursor moved, for hiding it in fullscreen.
+    cursor_moved_at: Instant,
+    cursor_hidden: bool,
+    /// Windowed geometry to save on exit, kept while fullscreen.
+    window_geometry: Option<WindowGeometry>,
+    /// Files are being dragged over the window.
+    drop_hovered: bool,
+    /// Files dropped since the last batch of events, one event each.
+    dropped: Vec<PathBuf>,
+    /// Watches the directory of the current image for live reload.
+    watcher: Option<Watcher>,
+    /// Changes to watched files waiting for writes to settle.
+    file_changes: Vec<(PathBuf, FileChange)>,
+    file_changed_at: Instant,
+    /// Grid of thumbnails drawn instead of the image while open.
+    gallery: Option<Gallery>,
+    /// Created the first time the gallery opens.
+    atlas: Option<ThumbnailAtlas>,
+    /// Decoded thumbnails waiting to be uploaded.
+    thumbnails: Vec<(PathBuf, image::RgbaImage)>,
+    /// Thumbnails on their way to the atlas.
+    thumbnail_uploads: Vec<PendingThumbnails>,
+    /// Files without a thumbnail, so they aren't tried again every frame.
+    thumbnail_errors: HashSet<PathBuf>,
+    /// Time and image of the last click in the gallery, for double clicks.
+    last_click: Option<(Instant, usize)>,
+}
+
+/// Textures holding every frame a layer each, as many per texture as the
+/// device allows, along with the size of the image they show, which is larger
+/// than the textures themselves when only a downscaled preview fit in VRAM.
+struct CachedTexture {
+    textures: Vec<TiledTexture>,
+    delays: Vec<Duration>,
+    image_size: Vec2,
+    orientation: Orientation,
+    /// Times the file asks an animation to play, or 0 for forever.
+    loop_count: u32,
+}
+
+/// Where the image being replaced was drawn, and which way it leaves.
+struct Outgoing {
+    path: PathBuf,
+    frame: usize,
+    transform: Transform,
+    /// -1 when going back through the playlist, 1 otherwise.
+    direction: f32,
+    started_at: Instant,
+}
+
+/// Thumbnails uploaded together, copied into the atlas once the upload is
+/// complete.
+struct PendingThumbnails {
+    /// One per thumbnail in the upload, or `None` once its file changed.
+    paths: Vec<Option<PathBuf>>,
+    upload: ThumbnailUpload,
 }
 
-impl ApplicationHandler for App {
+/// Frames of an image on their way into the texture cache.
+struct PendingUpload {
+    path: PathBuf,
+    textures: Vec<TextureUpload>,
+    delays: Vec<Duration>,
+    image_size: Vec2,
+    orientation: Orientation,
+    loop_count: u32,
+}
+
+impl ApplicationHandler<UserEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         self.init(event_loop);
     }
 
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.engine.free_retired();
+        self.update_cursor_visibility();
+        self.open_dropped();
+        self.apply_file_changes();
+        self.poll_uploads();
+        self.poll_thumbnail_uploads();
+        self.upload_missing_panes();
+        self.advance_slideshow();
+        self.advance_animation();
+        self.advance_transition();
+
+        // sleeps until input, a decoded image or the next timer
+        event_loop.set_control_flow(match self.next_wake_at() {
+            Some(deadline) => ControlFlow::WaitUntil(deadline),
+            None => ControlFlow::Wait,
+        });
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(geometry) = self.window_geometry
+            && let Err(e) = geometry.save()
+        {
+            eprintln!("Failed to save window geometry: {e}");
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::ImageDecoded { path, image } => self.on_image_decoded(path, image),
+            UserEvent::ImageChanged { path, image } => self.on_image_changed(path, image),
+            UserEvent::ThumbnailDecoded { path, image } => match image {
+                Ok(image) => {
+                    self.thumbnails.push((path, image));
+                    self.request_redraw();
+                }
+                Err(e) => {
+                    eprintln!("{}: {e}", path.display());
+                    self.thumbnail_errors.insert(path);
+                }
+            },
+            UserEvent::FileChanged { path, change } => {
+                self.file_changes.push((path, change));
+                self.file_changed_at = Instant::now();
+            }
+        }
+    }
+
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
         match event {
             WindowEvent::CloseRequested => {
@@ -24,10 +205,91 @@ impl ApplicationHandler for App {
             }
             WindowEvent::RedrawRequested => {
                 self.draw();
-                self.engine.window().request_redraw();
             }
             WindowEvent::Resized(_) => {
-                self.engine.recreate_swapchain();
+                self.recreate_swapchain();
+                self.track_window_geometry();
+            }
+            WindowEvent::Moved(_) => {
+                self.track_window_geometry();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_moved_at = Instant::now();
+                if self.cursor_hidden {
+                    self.engine.window().set_cursor_visible(true);
+                    self.cursor_hidden = false;
+                }
+
+                let position = vec2(position.x as f32, position.y as f32);
+                if self.dragging_divider {
+                    let viewport = self.viewport();
+                    if let Some(compare) = &mut self.compare {
+                        compare.set_divider(position.x, viewport);
+                    }
+                    self.request_redraw();
+                } else if self.dragging {
+                    self.camera.pan_by(position - self.cursor_position);
+                    self.request_redraw();
+                }
+                self.cursor_position = position;
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.gallery.is_some() => self.click_gallery(),
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } if self.dragging_divider
+                || state == ElementState::Pressed && self.is_over_divider() =>
+            {
+                self.dragging_divider = state == ElementState::Pressed;
+            }
+            WindowEvent::MouseInput { state, button, .. } => match self.bindings.mouse(button) {
+                Some(Action::Pan) => self.dragging = state == ElementState::Pressed,
+                Some(action) if state == ElementState::Pressed => self.perform(event_loop, action),
+                _ => (),
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => vec2(x, y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        vec2(position.x as f32, position.y as f32) / PIXELS_PER_SCROLL_LINE
+                    }
+                };
+                self.scroll(event_loop, lines);
+            }
+            WindowEvent::HoveredFile(_) => {
+                self.drop_hovered = true;
+                self.request_redraw();
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.drop_hovered = false;
+                self.request_redraw();
+            }
+            WindowEvent::DroppedFile(path) => {
+                self.drop_hovered = false;
+                self.dropped.push(path);
+                self.request_redraw();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let chord = KeyChord::new(logical_key, self.modifiers);
+                if let Some(action) = self.bindings.key(&chord) {
+                    self.perform(event_loop, action);
+                }
             }
             _ => (),
         }
@@ -35,18 +297,1115 @@ impl ApplicationHandler for App {
 }
 
 impl App {
-    pub fn new(ash_entry: ash::Entry) -> Self {
+    pub fn new(
+        ash_entry: ash::Entry,
+        settings: Settings,
+        bindings: Bindings,
+        playlist: Playlist,
+        image: DecodedImage,
+        proxy: EventLoopProxy<UserEvent>,
+    ) -> Self {
+        let thread_count = thread::available_parallelism()
+            .map_or(1, |x| x.get())
+            .min(MAX_DECODE_THREADS);
+
+        let watcher = match settings.live_reload.then(|| Watcher::new(proxy.clone())) {
+            Some(Ok(watcher)) => Some(watcher),
+            Some(Err(e)) => {
+                eprintln!("Live reload is unavailable: {e}");
+                None
+            }
+            None => None,
+        };
+
+        let mut pixel_cache = LruCache::new(settings.cache_size_bytes());
+        let size = image.size();
+        pixel_cache.insert(playlist.current().to_path_buf(), image, size);
+
         Self {
-            engine: Engine::new(ash_entry),
-            start_time: std::time::SystemTime::now(),
+            engine: Engine::new(ash_entry, &settings),
+            camera: Camera::new(settings.fit_mode, settings.pixel_art),
+            slideshow: Slideshow::new(!settings.slideshow),
+            compare: settings
+                .compare
+                .then(|| Compare::new(settings.compare_mode, settings.compare_count)),
+            texture_cache: LruCache::new(settings.cache_size_bytes()),
+            uploads: Vec::new(),
+            pixel_cache,
+            decoder: Decoder::new(
+                thread_count,
+                settings.source_alpha,
+                settings.cache_size_bytes(),
+                proxy,
+            ),
+            settings,
+            bindings,
+            playlist,
+            shown: None,
+            orientation: Orientation::default(),
+            shown_at: Instant::now(),
+            playback: Playback::new(),
+            outgoing: None,
+            cursor_position: Vec2::ZERO,
+            dragging: false,
+            dragging_divider: false,
+            modifiers: ModifiersState::empty(),
+            scroll_lines: Vec2::ZERO,
+            cursor_moved_at: Instant::now(),
+            cursor_hidden: false,
+            window_geometry: None,
+            drop_hovered: false,
+            dropped: Vec::new(),
+            watcher,
+            file_changes: Vec::new(),
+            file_changed_at: Instant::now(),
+            gallery: None,
+            atlas: None,
+            thumbnails: Vec::new(),
+            thumbnail_uploads: Vec::new(),
+            thumbnail_errors: HashSet::new(),
+            last_click: None,
         }
     }
 
     fn init(&mut self, event_loop: &ActiveEventLoop) {
-        self.engine.init(event_loop);
+        self.engine
+            .init(event_loop, self.window_attributes(event_loop));
+        self.track_window_geometry();
+        self.show_current();
+    }
+
+    fn window_attributes(&self, event_loop: &ActiveEventLoop) -> WindowAttributes {
+        let mut attributes = Window::default_attributes();
+        if let Some(geometry) = WindowGeometry::load() {
+            let monitors: Vec<_> = event_loop.available_monitors().collect();
+            attributes = geometry.apply(attributes, &monitors);
+        }
+        if self.settings.fullscreen {
+            attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        attributes
+    }
+
+    fn perform(&mut self, event_loop: &ActiveEventLoop, action: Action) {
+        // nearly every action changes what is on screen
+        self.request_redraw();
+        if self.perform_in_gallery(action) {
+            return;
+        }
+
+        let viewport = self.image_viewport();
+        match action {
+            Action::Next => self.navigate(|p| p.step(1)),
+            Action::Prev => self.navigate(|p| p.step(-1)),
+            Action::NextPage => self.navigate(|p| p.jump(PLAYLIST_PAGE_STEP)),
+            Action::PrevPage => self.navigate(|p| p.jump(-PLAYLIST_PAGE_STEP)),
+            Action::First => self.navigate(Playlist::first),
+            Action::Last => self.navigate(Playlist::last),
+            Action::ZoomIn => self.camera.zoom_at(ZOOM_STEP, viewport / 2.0, viewport),
+            Action::ZoomOut => self
+                .camera
+                .zoom_at(1.0 / ZOOM_STEP, viewport / 2.0, viewport),
+            Action::ResetZoom => self.set_fit_mode(self.camera.fit_mode()),
+            Action::Fit => self.set_fit_mode(FitMode::Fit),
+            Action::Fill => self.set_fit_mode(FitMode::Fill),
+            Action::ActualSize => self.set_fit_mode(FitMode::ActualSize),
+            Action::FitWidth => self.set_fit_mode(FitMode::FitWidth),
+            Action::FitHeight => self.set_fit_mode(FitMode::FitHeight),
+            Action::Pan => (),
+            Action::RotateCw => self.reorient(Orientation::rotate_cw),
+            Action::RotateCcw => self.reorient(Orientation::rotate_ccw),
+            Action::FlipHorizontal => self.reorient(Orientation::flip_horizontal),
+            Action::FlipVertical => self.reorient(Orientation::flip_vertical),
+            Action::CycleScaleFilter => {
+                self.settings.scale_filter = self.settings.scale_filter.next()
+            }
+            Action::TogglePixelArt => self.toggle_pixel_art(),
+            Action::TogglePixelGrid => self.settings.pixel_grid = !self.settings.pixel_grid,
+            Action::CycleBackground => self.cycle_background(),
+            Action::SmallerCheckers => self.resize_checkers(0.5),
+            Action::LargerCheckers => self.resize_checkers(2.0),
+            Action::ToggleFitWindow => self.toggle_fit_window(),
+            Action::ToggleFullscreen => self.toggle_fullscreen(),
+            Action::ExitFullscreen if self.is_fullscreen() => self.toggle_fullscreen(),
+            Action::ExitFullscreen => (),
+            Action::MemoryStats => {
+                let stats = format!("GPU memory: {}", self.engine.memory_stats());
+                self.update_title(Some(&stats));
+            }
+            Action::TogglePlayback => {
+                self.playback.toggle_pause(self.loop_count());
+                self.update_playback_title();
+            }
+            Action::NextFrame => self.step_frame(1),
+            Action::PrevFrame => self.step_frame(-1),
+            Action::FasterPlayback => self.change_playback_speed(PLAYBACK_SPEED_STEP),
+            Action::SlowerPlayback => self.change_playback_speed(1.0 / PLAYBACK_SPEED_STEP),
+            Action::ToggleSlideshow => self.toggle_slideshow(),
+            Action::ToggleCompare => self.toggle_compare(),
+            Action::CycleCompareMode => self.cycle_compare_mode(),
+            Action::ToggleGallery => self.open_gallery(),
+            Action::Open | Action::PrevRow | Action::NextRow => (),
+            Action::Quit => event_loop.exit(),
+        }
+    }
+
+    /// Handles the actions that move around the gallery while it is open,
+    /// returning whether `action` was one of them.
+    fn perform_in_gallery(&mut self, action: Action) -> bool {
+        let len = self.playlist.len();
+        let viewport = self.viewport();
+        let Some(gallery) = &mut self.gallery else {
+            return false;
+        };
+
+        let page = Gallery::page_rows(viewport);
+        match action {
+            Action::Next => gallery.move_selection(1, len, viewport),
+            Action::Prev => gallery.move_selection(-1, len, viewport),
+            Action::NextRow => gallery.move_rows(1, len, viewport),
+            Action::PrevRow => gallery.move_rows(-1, len, viewport),
+            Action::NextPage => gallery.move_rows(page, len, viewport),
+            Action::PrevPage => gallery.move_rows(-page, len, viewport),
+            Action::First => gallery.select(0, len, viewport),
+            Action::Last => gallery.select(len as isize - 1, len, viewport),
+            Action::Open => self.open_selected(),
+            Action::ToggleGallery => self.close_gallery(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Shows the playlist as thumbnails, starting at the current image.
+    fn open_gallery(&mut self) {
+        self.fit_atlas();
+        if self.atlas.is_none() {
+            return;
+        }
+
+        let index = self.playlist.index();
+        let mut gallery = Gallery::new(index);
+        gallery.select(index as isize, self.playlist.len(), self.viewport());
+        self.gallery = Some(gallery);
+        self.outgoing = None;
+        self.update_title(None);
+    }
+
+    /// Grows the thumbnail atlas to hold two windows full of thumbnails, so
+    /// those on screen never make way for each other and paging back doesn't
+    /// ask for them again. Keeps the old atlas if a larger one can't be made.
+    fn fit_atlas(&mut self) {
+        let slot_count = 2 * Gallery::max_visible(self.viewport());
+        let size = self.engine.thumbnail_atlas_size(slot_count);
+        if let Some(atlas) = &self.atlas
+            && atlas.texture().extent().width >= size
+        {
+            return;
+        }
+
+        match self.engine.create_thumbnail_atlas(size) {
+            Ok(atlas) => {
+                if let Some(old) = self.atlas.replace(atlas) {
+                    self.engine.destroy_thumbnail_atlas(old);
+                }
+            }
+            Err(e) => eprintln!("Failed to create the thumbnail atlas: {e}"),
+        }
+    }
+
+    fn close_gallery(&mut self) {
+        self.gallery = None;
+        self.last_click = None;
+        self.decoder.request_thumbnails([]);
+        self.show_current();
+    }
+
+    fn open_selected(&mut self) {
+        if let Some(gallery) = &self.gallery {
+            self.playlist.go_to(gallery.selected());
+            self.close_gallery();
+        }
+    }
+
+    /// Selects the thumbnail under the cursor, and opens it on a double click.
+    fn click_gallery(&mut self) {
+        let len = self.playlist.len();
+        let viewport = self.viewport();
+        let Some(gallery) = &mut self.gallery else {
+            return;
+        };
+        let Some(index) = gallery.index_at(self.cursor_position, len, viewport) else {
+            return;
+        };
+        gallery.select(index as isize, len, viewport);
+        self.request_redraw();
+
+        let double_click = self
+            .last_click
+            .is_some_and(|(at, i)| i == index && at.elapsed() < DOUBLE_CLICK_TIME);
+        if double_click {
+            self.open_selected();
+        } else {
+            self.last_click = Some((Instant::now(), index));
+        }
+    }
+
+    /// Scrolls by `lines` along each axis. Zooming follows the wheel smoothly
+    /// around the cursor, other actions fire once per whole line.
+    fn scroll(&mut self, event_loop: &ActiveEventLoop, lines: Vec2) {
+        let len = self.playlist.len();
+        let viewport = self.viewport();
+        if let Some(gallery) = &mut self.gallery {
+            gallery.scroll_by(-lines.y * PIXELS_PER_SCROLL_LINE, len, viewport);
+            self.request_redraw();
+            return;
+        }
+
+        self.scroll_lines += lines;
+
+        let axes = [
+            (lines.y, WheelDirection::Up, WheelDirection::Down),
+            (lines.x, WheelDirection::Left, WheelDirection::Right),
+        ];
+        for (i, (delta, positive, negative)) in axes.into_iter().enumerate() {
+            if delta == 0.0 {
+                continue;
+            }
+            let direction = if delta > 0.0 { positive } else { negative };
+            let Some(action) = self.bindings.wheel(direction) else {
+                continue;
+            };
+
+            match action {
+                Action::ZoomIn | Action::ZoomOut => {
+                    let sign = if action == Action::ZoomIn { 1.0 } else { -1.0 };
+                    let factor = ZOOM_STEP.powf(sign * delta.abs());
+                    let viewport = self.image_viewport();
+                    self.camera.zoom_at(factor, self.pane_cursor(), viewport);
+                    self.scroll_lines[i] = 0.0;
+                    self.request_redraw();
+                }
+                _ => {
+                    while self.scroll_lines[i].abs() >= 1.0 {
+                        self.scroll_lines[i] -= self.scroll_lines[i].signum();
+                        self.perform(event_loop, action);
+                    }
+                }
+            }
+        }
+    }
+
+    fn navigate(&mut self, f: impl FnOnce(&mut Playlist)) {
+        let before = self.playlist.index();
+        f(&mut self.playlist);
+        if self.playlist.index() != before {
+            self.show_current();
+        }
+    }
+
+    /// Replaces the playlist with the files dropped onto the window, the
+    /// same way as paths given on the command line.
+    fn open_dropped(&mut self) {
+        if self.dropped.is_empty() {
+            return;
+        }
+        let paths = mem::take(&mut self.dropped);
+
+        match Playlist::from_paths(&paths, self.settings.recursive, self.settings.sort) {
+            Ok(playlist) => {
+                self.playlist = playlist;
+                self.show_current();
+            }
+            Err(e) => {
+                eprintln!("{}: {e}", paths[0].display());
+                self.update_title(Some(&e.to_string()));
+            }
+        }
+    }
+
+    fn on_image_decoded(&mut self, path: PathBuf, image: image::ImageResult<DecodedImage>) {
+        match image {
+            Ok(image) => {
+                let is_shown = self.compared_paths().contains(&path);
+                let size = image.size();
+                self.pixel_cache.insert(path, image, size);
+                if is_shown {
+                    self.show_current();
+                }
+            }
+            Err(e) => self.report_error(&path, &e),
+        }
+    }
+
+    /// Takes in a new version of the image at `path`. A shown image stays up
+    /// until its new texture is uploaded, other textures are just dropped.
+    fn on_image_changed(&mut self, path: PathBuf, image: image::ImageResult<DecodedImage>) {
+        self.forget_thumbnail(&path);
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => return self.report_error(&path, &e),
+        };
+
+        let size = image.size();
+        self.pixel_cache.insert(path.clone(), image, size);
+        if self.compared_paths().contains(&path) {
+            self.begin_upload(path);
+        } else if let Some(cached) = self.texture_cache.remove(&path) {
+            cached.destroy(&mut self.engine);
+        }
+    }
+
+    /// Brings the playlist and caches up to date once the watched directory
+    /// has been quiet for a while, so half-written files aren't read. The
+    /// current image and view stay put unless the image is gone.
+    fn apply_file_changes(&mut self) {
+        if self.file_changes.is_empty() || self.file_changed_at.elapsed() < RELOAD_DEBOUNCE {
+            return;
+        }
+
+        let mut dirs = Vec::new();
+        let mut touched = Vec::new();
+        for (path, change) in mem::take(&mut self.file_changes) {
+            let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            match change {
+                FileChange::Modified => (),
+                FileChange::Added | FileChange::Removed => dirs.push(dir),
+                FileChange::Renamed(to) => {
+                    self.rename(&path, &to);
+                    dirs.push(dir);
+                    touched.push(to);
+                }
+            }
+            touched.push(path);
+        }
+
+        dirs.sort();
+        dirs.dedup();
+        dirs.retain(|x| self.playlist.lists(x));
+        for dir in dirs {
+            if let Err(e) = self.playlist.rescan(&dir, self.settings.sort) {
+                eprintln!("{}: {e}", dir.display());
+            }
+        }
+
+        touched.sort();
+        touched.dedup();
+        for path in touched {
+            self.forget_thumbnail(&path);
+            if path.is_file() {
+                // only what is cached needs decoding again now
+                if self.pixel_cache.contains(&path) || self.texture_cache.contains(&path) {
+                    self.decoder.reload(path);
+                }
+            } else if !path.exists() {
+                self.forget(&path);
+            }
+        }
+
+        self.show_current();
+        self.request_redraw();
+    }
+
+    /// Follows a file renamed on disk, so it keeps its place and caches.
+    fn rename(&mut self, from: &Path, to: &Path) {
+        self.playlist.rename(from, to);
+        self.pixel_cache
+            .rename(&from.to_path_buf(), to.to_path_buf());
+        if let Some(replaced) = self
+            .texture_cache
+            .rename(&from.to_path_buf(), to.to_path_buf())
+        {
+            replaced.destroy(&mut self.engine);
+        }
+        if self.shown.as_deref() == Some(from) {
+            self.shown = Some(to.to_path_buf());
+        }
+    }
+
+    /// Drops a deleted file from the playlist and caches. Its texture stays
+    /// while shown, until whatever replaces it is ready.
+    fn forget(&mut self, path: &Path) {
+        self.playlist.remove(path);
+        let path = path.to_path_buf();
+        self.pixel_cache.remove(&path);
+        if self.shown.as_ref() != Some(&path)
+            && let Some(cached) = self.texture_cache.remove(&path)
+        {
+            cached.destroy(&mut self.engine);
+        }
+    }
+
+    /// Drops the thumbnail of a changed file so the gallery makes a new one.
+    fn forget_thumbnail(&mut self, path: &Path) {
+        if let Some(atlas) = &mut self.atlas {
+            atlas.remove(path);
+        }
+        self.thumbnails.retain(|(x, _)| x != path);
+        for pending_path in self.thumbnail_uploads.iter_mut().flat_map(|x| &mut x.paths) {
+            if pending_path.as_deref() == Some(path) {
+                *pending_path = None;
+            }
+        }
+        self.thumbnail_errors.remove(path);
+    }
+
+    /// Points the watcher at the directory of the current image. Pipes and
+    /// standard input have nothing to reload.
+    fn watch_current_dir(&mut self) {
+        let current = self.playlist.current();
+        if let Some(watcher) = &mut self.watcher
+            && current.is_file()
+        {
+            watcher.watch(current.parent().unwrap_or(Path::new("")));
+        }
+    }
+
+    fn report_error(&self, path: &Path, e: &dyn Error) {
+        eprintln!("{}: {e}", path.display());
+        if path == self.playlist.current() {
+            self.update_title(Some(&e.to_string()));
+        }
+    }
+
+    /// Shows the playlist's current image if it is decoded, and queues the
+    /// decoding of whatever is missing around it.
+    fn show_current(&mut self) {
+        let path = self.playlist.current().to_path_buf();
+        self.watch_current_dir();
+
+        if self.texture_cache.contains(&path) {
+            self.display(path);
+        } else {
+            self.update_title(Some("loading"));
+            self.upload_if_decoded(path);
+        }
+        // the images compared with it are drawn as soon as they are ready
+        if self.compare.is_some() {
+            self.upload_missing_panes();
+            self.request_redraw();
+        }
+
+        self.prefetch();
+
+        // the playlist may have shrunk under the gallery
+        let len = self.playlist.len();
+        let viewport = self.viewport();
+        if let Some(gallery) = &mut self.gallery {
+            gallery.select(gallery.selected() as isize, len, viewport);
+        }
+    }
+
+    /// Uploads the images compared with the current one that have no texture,
+    /// whether they were never uploaded or lost theirs, and marks the others
+    /// as used.
+    fn upload_missing_panes(&mut self) {
+        if self.compare.is_none() {
+            return;
+        }
+        for path in self.compared_paths().into_iter().skip(1) {
+            if self.texture_cache.get(&path).is_none() {
+                self.upload_if_decoded(path);
+            }
+        }
+    }
+
+    /// Starts uploading the image at `path` unless it is not decoded yet or
+    /// already on its way.
+    fn upload_if_decoded(&mut self, path: PathBuf) {
+        let uploading = self.uploads.iter().any(|x| x.path == path);
+        if self.pixel_cache.contains(&path) && !uploading {
+            self.begin_upload(path);
+        }
+    }
+
+    /// Starts uploading every frame of the decoded image at `path`. Cached
+    /// textures are evicted to make room for it, and if it does not fit even
+    /// on its own a downscaled preview is uploaded instead.
+    fn begin_upload(&mut self, path: PathBuf) {
+        // images on screen stay, whatever else has to go
+        let pinned = self.pinned_paths();
+        let keep = |x: &PathBuf| pinned.contains(x);
+
+        let Some(decoded) = self.pixel_cache.get(&path) else {
+            return;
+        };
+        let orientation = decoded.orientation;
+        let delays = decoded.delays.clone();
+        let loop_count = decoded.loop_count;
+        let frames: Vec<_> = decoded.frames().collect();
+        let image = frames[0];
+        let image_size = vec2(image.width() as f32, image.height() as f32);
+        // a third more for the mip chain
+        let required = decoded.size() * 4 / 3;
+
+        // evict ahead of time when the driver tells us how much is left,
+        // counting what the victims will give back before freeing them at once
+        self.engine.free_retired();
+        let mut available = self.engine.available_memory();
+        let mut evicted = Vec::new();
+        while let Some(free) = available
+            && free < required
+            && let Some(cached) = self.texture_cache.pop_lru_except(keep)
+        {
+            available = Some(free + cached.size());
+            evicted.push(cached);
+        }
+        for cached in evicted {
+            cached.destroy(&mut self.engine);
+        }
+        let mut scale = match available {
+            Some(available) if available < required => {
+                (available as f64 / required as f64 * PREVIEW_BUDGET_FRACTION).sqrt()
+            }
+            _ => 1.0,
+        };
+
+        // otherwise find out when the allocation fails
+        let uploads = loop {
+            match begin_frame_uploads(&mut self.engine, &frames, scale) {
+                Ok(uploads) => break uploads,
+                // evicted textures only give their memory back once freed
+                Err(_) if self.engine.has_retired() => {
+                    self.engine.wait_retired();
+                }
+                Err(_) if let Some(cached) = self.texture_cache.pop_lru_except(keep) => {
+                    cached.destroy(&mut self.engine);
+                }
+                Err(_) if image.width().min(image.height()) as f64 * scale >= 2.0 => {
+                    scale /= 2.0;
+                }
+                Err(e) => {
+                    eprintln!("{}: {e}", path.display());
+                    self.update_title(Some(&e.to_string()));
+                    return;
+                }
+            }
+        };
+
+        self.uploads.push(PendingUpload {
+            path,
+            textures: uploads,
+            delays,
+            image_size,
+            orientation,
+            loop_count,
+        });
+    }
+
+    fn display(&mut self, path: PathBuf) {
+        let Some(cached) = self.texture_cache.get(&path) else {
+            return;
+        };
+        let preview = cached.is_preview();
+        let orientation = cached.orientation;
+        let frame_count = cached.frame_count();
+        self.update_title(preview.then_some("downscaled preview"));
+        self.request_redraw();
+
+        // a new version of the shown image keeps the view
+        if self.shown.as_ref() == Some(&path) {
+            self.playback.clamp(frame_count);
+            let image_size = self.displayed_size();
+            let viewport = self.image_viewport();
+            self.camera.update_fit(image_size, viewport);
+            return;
+        }
+        self.begin_transition();
+        self.orientation = orientation;
+        self.shown = Some(path);
+        self.playback.restart();
+        self.set_fit_mode(self.camera.fit_mode());
+        if self.settings.fit_window {
+            self.fit_window_to_image();
+        }
+        // only the first image starts at the requested zoom
+        if let Some(zoom) = self.settings.zoom.take() {
+            let viewport = self.image_viewport();
+            self.camera
+                .zoom_at(zoom / self.camera.zoom(), viewport / 2.0, viewport);
+        }
+        self.shown_at = Instant::now();
+    }
+
+    /// Moves to the next image once the shown one has been up for the
+    /// slideshow interval, or stops after the last one unless looping.
+    fn advance_slideshow(&mut self) {
+        if self.next_slide_at().is_none_or(|x| x > Instant::now()) {
+            return;
+        }
+        // a playlist of one image waits another interval
+        self.shown_at = Instant::now();
+        match self.slideshow_upcoming() {
+            Some(path) => self.navigate(|p| p.go_to(p.position(&path).unwrap_or(p.index()))),
+            None => {
+                self.slideshow.pause();
+                self.update_title(Some("slideshow ended"));
+            }
+        }
+    }
+
+    /// When the slideshow moves on from the shown image, if it is running.
+    fn next_slide_at(&self) -> Option<Instant> {
+        let showing_current = self
+            .shown
+            .as_ref()
+            .is_some_and(|x| x == self.playlist.current());
+
+        let running = !self.slideshow.is_paused() && self.gallery.is_none();
+        // an interval too long to represent never comes
+        let interval = Duration::try_from_secs_f64(self.settings.slideshow_interval).ok()?;
+        (running && showing_current)
+            .then(|| self.shown_at.checked_add(interval))
+            .flatten()
+    }
+
+    fn slideshow_upcoming(&mut self) -> Option<PathBuf> {
+        self.slideshow.upcoming(
+            &self.playlist,
+            self.settings.slideshow_shuffle,
+            self.settings.slideshow_loop,
+        )
+    }
+
+    /// Pauses or resumes the slideshow. Resuming one that ended starts it
+    /// over.
+    fn toggle_slideshow(&mut self) {
+        self.slideshow.toggle_pause();
+        if self.slideshow.is_paused() {
+            self.update_title(Some("slideshow paused"));
+            return;
+        }
+
+        self.update_title(Some("slideshow"));
+        self.shown_at = Instant::now();
+        if self.slideshow_upcoming().is_none() {
+            self.slideshow.restart();
+            if !self.settings.slideshow_shuffle {
+                self.navigate(Playlist::first);
+            }
+        }
+    }
+
+    /// Compares the current image with the ones after it, or goes back to
+    /// showing it alone.
+    fn toggle_compare(&mut self) {
+        self.compare = match self.compare {
+            Some(_) => None,
+            None => Some(Compare::new(
+                self.settings.compare_mode,
+                self.settings.compare_count,
+            )),
+        };
+        self.relayout_compare();
+    }
+
+    fn cycle_compare_mode(&mut self) {
+        if let Some(compare) = &mut self.compare {
+            compare.cycle_mode();
+            self.relayout_compare();
+        }
+    }
+
+    /// Fits the image to the area it now has, and loads the images
+    /// compared with it.
+    fn relayout_compare(&mut self) {
+        self.dragging_divider = false;
+        self.outgoing = None;
+        let image_size = self.displayed_size();
+        let viewport = self.image_viewport();
+        self.camera.update_fit(image_size, viewport);
+        self.show_current();
+    }
+
+    /// Keeps the shown image on screen, where it is, while the running
+    /// slideshow changes to the next one.
+    fn begin_transition(&mut self) {
+        self.outgoing = None;
+        let running =
+            !self.slideshow.is_paused() && self.gallery.is_none() && self.compare.is_none();
+        if !running
+            || self.settings.transition == Transition::None
+            || self.settings.transition_duration <= 0.0
+        {
+            return;
+        }
+        let Some(path) = self.shown.clone() else {
+            return;
+        };
+
+        // stepping back slides the other way
+        let back = self.playlist.peek(1) == path && self.playlist.peek(-1) != path;
+        self.outgoing = Some(Outgoing {
+            path,
+            frame: self.playback.frame(),
+            transform: self.image_transform(),
+            direction: if back { -1.0 } else { 1.0 },
+            started_at: Instant::now(),
+        });
+    }
+
+    /// Redraws while a transition plays, and ends it once its time is up or
+    /// the outgoing image is gone from the cache.
+    fn advance_transition(&mut self) {
+        let Some(outgoing) = &self.outgoing else {
+            return;
+        };
+        if outgoing.started_at.elapsed().as_secs_f64() >= self.settings.transition_duration
+            || self.outgoing_texture().is_none()
+        {
+            self.outgoing = None;
+        }
+        self.request_redraw();
+    }
+
+    /// Shows the next frame of an animation once the current one has been up
+    /// for its delay.
+    fn advance_animation(&mut self) {
+        if self.gallery.is_some() {
+            return;
+        }
+        let loop_count = self.loop_count();
+        let Some(cached) = self.shown.as_ref().and_then(|x| self.texture_cache.peek(x)) else {
+            return;
+        };
+
+        if self.playback.advance(&cached.delays, loop_count) {
+            self.request_redraw();
+        }
+    }
+
+    /// When the shown animation moves to its next frame, if it is playing.
+    fn next_frame_at(&self) -> Option<Instant> {
+        if self.gallery.is_some() {
+            return None;
+        }
+        let cached = self.texture_cache.peek(self.shown.as_ref()?)?;
+        self.playback
+            .next_frame_at(&cached.delays, self.loop_count())
+    }
+
+    /// Times the shown animation plays, or 0 for forever: as set, otherwise
+    /// as its file asks.
+    fn loop_count(&self) -> u32 {
+        let file_loop_count = self
+            .shown
+            .as_ref()
+            .and_then(|path| self.texture_cache.peek(path))
+            .map_or(0, |cached| cached.loop_count);
+        self.settings.loop_count.unwrap_or(file_loop_count)
+    }
+
+    /// Earliest time something changes without an event announcing it: the
+    /// next animation frame or slide, hiding the cursor, settled file changes,
+    /// or checking on uploads.
+    fn next_wake_at(&self) -> Option<Instant> {
+        let cursor_hide_at = (self.is_fullscreen() && !self.cursor_hidden)
+            .then(|| self.cursor_moved_at + CURSOR_HIDE_DELAY);
+        let reload_at =
+            (!self.file_changes.is_empty()).then(|| self.file_changed_at + RELOAD_DEBOUNCE);
+        let uploading = !self.uploads.is_empty()
+            || (self.gallery.is_some() && !self.thumbnail_uploads.is_empty());
+        let upload_poll_at = uploading.then(|| Instant::now() + UPLOAD_POLL_INTERVAL);
+
+        [
+            self.next_frame_at(),
+            self.next_slide_at(),
+            cursor_hide_at,
+            reload_at,
+            upload_poll_at,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Pauses the shown animation and moves `offset` frames.
+    fn step_frame(&mut self, offset: isize) {
+        let frame_count = self.shown_frame_count();
+        if frame_count > 1 {
+            self.playback.step(offset, frame_count);
+            self.update_playback_title();
+        }
+    }
+
+    fn change_playback_speed(&mut self, factor: f32) {
+        self.playback.change_speed(factor);
+        self.update_playback_title();
+    }
+
+    /// Shows the frame, when paused, and the speed of an animation in the
+    /// title.
+    fn update_playback_title(&self) {
+        let frame_count = self.shown_frame_count();
+        if frame_count < 2 {
+            return;
+        }
+
+        let mut status = Vec::new();
+        if self.playback.is_paused() {
+            status.push(format!(
+                "paused at frame {}/{frame_count}",
+                self.playback.frame() + 1
+            ));
+        }
+        if self.playback.speed() != 1.0 {
+            status.push(format!("{}x speed", self.playback.speed()));
+        }
+        let status = status.join(", ");
+        self.update_title((!status.is_empty()).then_some(&status));
+    }
+
+    fn shown_frame_count(&self) -> usize {
+        self.shown
+            .as_ref()
+            .and_then(|path| self.texture_cache.peek(path))
+            .map_or(0, CachedTexture::frame_count)
+    }
+
+    /// Redraws the gallery once thumbnails are ready to go into the atlas.
+    fn poll_thumbnail_uploads(&self) {
+        let ready = self
+            .thumbnail_uploads
+            .iter()
+            .any(|x| self.engine.is_thumbnail_upload_complete(&x.upload));
+        if self.gallery.is_some() && ready {
+            self.request_redraw();
+        }
+    }
+
+    /// Moves finished uploads into the texture cache.
+    fn poll_uploads(&mut self) {
+        let (done, pending): (Vec<_>, Vec<_>) =
+            mem::take(&mut self.uploads)
+                .into_iter()
+                .partition(|pending| {
+                    pending
+                        .textures
+                        .iter()
+                        .all(|x| self.engine.is_upload_complete(x))
+                });
+        self.uploads = pending;
+
+        for pending in done {
+            let path = pending.path;
+            let textures: Vec<_> = pending
+                .textures
+                .into_iter()
+                .map(|x| self.engine.finish_texture_upload(x))
+                .collect();
+            let cached = CachedTexture {
+                textures,
+                delays: pending.delays,
+                image_size: pending.image_size,
+                orientation: pending.orientation,
+                loop_count: pending.loop_count,
+            };
+            let size = cached.size();
+            let pinned = self.pinned_paths();
+            let evicted = self
+                .texture_cache
+                .insert_keeping(path.clone(), cached, size, |x| pinned.contains(x));
+            for cached in evicted {
+                cached.destroy(&mut self.engine);
+            }
+
+            if path == self.playlist.current() {
+                self.display(path);
+            } else if self.compared_paths().contains(&path) {
+                self.request_redraw();
+            }
+        }
+    }
+
+    fn prefetch(&mut self) {
+        // a shuffled slideshow goes anywhere next
+        let upcoming = if self.slideshow.is_paused() {
+            None
+        } else {
+            self.slideshow_upcoming()
+        };
+        let offsets = (1..=self.settings.prefetch as isize).flat_map(|i| [i, -i]);
+        let paths = self
+            .compared_paths()
+            .into_iter()
+            .chain(offsets.map(|offset| self.playlist.peek(offset).to_path_buf()))
+            .chain(upcoming)
+            .filter(|path| !self.pixel_cache.contains(path) && !self.texture_cache.contains(path));
+
+        self.decoder.request(paths);
+    }
+
+    fn update_title(&self, status: Option<&str>) {
+        let file_name = self
+            .compared_paths()
+            .iter()
+            .map(|x| x.file_name().unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let status = if self.gallery.is_some() {
+            Some("gallery")
+        } else {
+            status
+        };
+        let status = status.map(|x| format!(" ({x})")).unwrap_or_default();
+
+        self.engine.window().set_title(&format!(
+            "{file_name} [{}/{}]{status} - Image Viewer",
+            self.playlist.index() + 1,
+            self.playlist.len(),
+        ));
+    }
+
+    fn recreate_swapchain(&mut self) {
+        // drawing without a swapchain would only fail and get here again
+        if !self.engine.recreate_swapchain() {
+            return;
+        }
+        self.request_redraw();
+        if self.gallery.is_some() {
+            self.fit_atlas();
+        }
+
+        let image_size = self.displayed_size();
+        let viewport = self.image_viewport();
+        self.camera.update_fit(image_size, viewport);
+    }
+
+    fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        let image_size = self.displayed_size();
+        let viewport = self.image_viewport();
+        self.camera.set_fit_mode(fit_mode, image_size, viewport);
+    }
+
+    fn reorient(&mut self, f: impl FnOnce(Orientation) -> Orientation) {
+        self.orientation = f(self.orientation);
+
+        let image_size = self.displayed_size();
+        let viewport = self.image_viewport();
+        self.camera.update_fit(image_size, viewport);
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.engine.window().fullscreen().is_some()
+    }
+
+    /// Switches between a window and borderless fullscreen on the monitor the
+    /// window is on.
+    fn toggle_fullscreen(&mut self) {
+        let window = self.engine.window();
+        if self.is_fullscreen() {
+            window.set_fullscreen(None);
+            window.set_cursor_visible(true);
+            self.cursor_hidden = false;
+        } else {
+            window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+            self.cursor_moved_at = Instant::now();
+        }
+    }
+
+    fn toggle_fit_window(&mut self) {
+        self.settings.fit_window = !self.settings.fit_window;
+        if self.settings.fit_window {
+            self.fit_window_to_image();
+        }
+    }
+
+    /// Resizes the window to show the image at its natural size, shrunk to
+    /// fit the monitor. winit doesn't report the work area, so some of the
+    /// monitor is left for panels and decorations.
+    fn fit_window_to_image(&mut self) {
+        let window = self.engine.window();
+        if self.is_fullscreen() {
+            return;
+        }
+        let Some(monitor) = window.current_monitor() else {
+            return;
+        };
+
+        let monitor_size = monitor.size();
+        let max_size =
+            vec2(monitor_size.width as f32, monitor_size.height as f32) * MAX_WINDOW_FRACTION;
+        let image_size = self.displayed_size();
+        let size = image_size * (max_size / image_size).min_element().min(1.0);
+
+        // a size applied right away still sends a Resized event
+        let _ = window.request_inner_size(PhysicalSize::new(
+            (size.x.round() as u32).max(1),
+            (size.y.round() as u32).max(1),
+        ));
+    }
+
+    fn track_window_geometry(&mut self) {
+        let window = self.engine.window();
+        // a minimized window reports a zero size, and maybe a position far
+        // off screen
+        let size = window.inner_size();
+        if !self.is_fullscreen()
+            && window.is_minimized() != Some(true)
+            && size.width > 0
+            && size.height > 0
+        {
+            self.window_geometry = Some(WindowGeometry::of(window));
+        }
+    }
+
+    /// Hides the cursor once it has rested for a while in fullscreen.
+    fn update_cursor_visibility(&mut self) {
+        if self.is_fullscreen()
+            && !self.cursor_hidden
+            && self.cursor_moved_at.elapsed() >= CURSOR_HIDE_DELAY
+        {
+            self.engine.window().set_cursor_visible(false);
+            self.cursor_hidden = true;
+        }
+    }
+
+    fn toggle_pixel_art(&mut self) {
+        self.settings.pixel_art = !self.settings.pixel_art;
+
+        let image_size = self.displayed_size();
+        let viewport = self.image_viewport();
+        self.camera
+            .set_pixel_snap(self.settings.pixel_art, image_size, viewport);
+    }
+
+    fn cycle_background(&mut self) {
+        let mut background = self.settings.background.next();
+        if background == Background::Transparent && !self.engine.supports_transparency() {
+            background = background.next();
+        }
+        self.settings.background = background;
+    }
+
+    fn resize_checkers(&mut self, factor: f32) {
+        let size = (self.settings.checker_size as f32 * factor) as u32;
+        self.settings.checker_size = size.clamp(MIN_CHECKER_SIZE, MAX_CHECKER_SIZE);
+    }
+
+    /// Linear color the frame is cleared to, with alpha 0 only for a
+    /// transparent window.
+    fn clear_color(&self) -> [f32; 4] {
+        if self.settings.background == Background::Transparent
+            && self.engine.supports_transparency()
+        {
+            return [0.0; 4];
+        }
+
+        let [r, g, b] = self
+            .settings
+            .background_color
+            .map(|x| color::srgb_to_linear(x as f32 / 255.0));
+        [r, g, b, 1.0]
+    }
+
+    fn request_redraw(&self) {
+        self.engine.window().request_redraw();
     }
 
     fn draw(&mut self) {
+        if self.shown_texture().is_none() && self.gallery.is_none() {
+            return;
+        }
         let engine = &self.engine;
         let device = engine.device();
         let in_flight_fence = engine.in_flight_fence();
@@ -66,7 +1425,7 @@ impl App {
             ) {
                 Ok(t) => t,
                 Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
-                    self.engine.recreate_swapchain();
+                    self.recreate_swapchain();
                     return;
                 }
                 _ => panic!(),
@@ -115,10 +1474,10 @@ impl App {
             {
                 Ok(is_suboptimal) => {
                     if is_suboptimal {
-                        self.engine.recreate_swapchain()
+                        self.recreate_swapchain()
                     }
                 }
-                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.engine.recreate_swapchain(),
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.recreate_swapchain(),
                 _ => panic!(),
             }
         }
@@ -127,28 +1486,38 @@ impl App {
     }
 
     fn record_command_buffer(&mut self, command_buffer: vk::CommandBuffer, image_index: usize) {
+        let begin_info = vk::CommandBufferBeginInfo::default();
+        unsafe {
+            self.engine
+                .device()
+                .begin_command_buffer(command_buffer, &begin_info)
+                .unwrap()
+        };
+        // copies thumbnails into the atlas ahead of the render pass
+        let gallery_instances = self
+            .gallery
+            .is_some()
+            .then(|| self.prepare_gallery(command_buffer));
+
         let engine = &self.engine;
         let device = engine.device();
         let render_pass = engine.render_pass();
         let swapchain = engine.swapchain();
-        let pipeline = engine.graphics_pipeline();
-        let vertex_buffer = engine.vertex_buffer();
-        let index_buffer = engine.index_buffer();
-
-        let layout = pipeline.layout();
-        let pipeline = pipeline.pipeline();
-
         let framebuffer = engine.framebuffer(image_index);
 
         unsafe {
-            let begin_info = vk::CommandBufferBeginInfo::default();
-            device
-                .begin_command_buffer(command_buffer, &begin_info)
-                .unwrap();
-
             let clear_values = [{
                 let mut clear_color = vk::ClearValue::default();
-                clear_color.color.float32 = [0.0, 0.0, 0.0, 1.0];
+                // a transition adds up its images first and fills in the
+                // background behind them after
+                let transitioning = self.outgoing_texture().is_some()
+                    && gallery_instances.is_none()
+                    && self.compare.is_none();
+                clear_color.color.float32 = if transitioning {
+                    [0.0; 4]
+                } else {
+                    self.clear_color()
+                };
                 clear_color
             }];
             let render_pass_info = vk::RenderPassBeginInfo::default()
@@ -165,65 +1534,528 @@ impl App {
                 &render_pass_info,
                 vk::SubpassContents::INLINE,
             );
+            let window = (Vec2::ZERO, self.viewport());
+            self.set_viewport(command_buffer, window, window);
+
+            match gallery_instances {
+                Some(instance_count) => self.record_gallery(command_buffer, instance_count),
+                None if self.compare.is_some() => self.record_compare(command_buffer),
+                None => self.record_images(command_buffer),
+            }
+            if self.drop_hovered {
+                self.record_drop_indicator(command_buffer);
+            }
+            device.cmd_end_render_pass(command_buffer);
+            device.end_command_buffer(command_buffer).unwrap();
+        }
+    }
+
+    /// Draws the shown image, mixed with the one it replaces while a
+    /// transition plays.
+    fn record_images(&self, command_buffer: vk::CommandBuffer) {
+        let shown = self.shown_texture().unwrap();
+        let graphics_pipeline = self.engine.graphics_pipeline();
+        // the outgoing image may have been evicted or forgotten since, which
+        // ends the transition in `advance_transition`
+        let (Some(outgoing), Some(texture)) = (&self.outgoing, self.outgoing_texture()) else {
+            return self.record_image(command_buffer, graphics_pipeline, shown, 0, Layer::OPAQUE);
+        };
+
+        let elapsed = outgoing.started_at.elapsed().as_secs_f64();
+        let progress = (elapsed / self.settings.transition_duration) as f32;
+        let [from, to] = self
+            .settings
+            .transition
+            .layers(progress, outgoing.direction);
+        // the layers are weighted to add up to one image, over a background
+        // drawn once behind both
+        let additive_pipeline = self.engine.additive_pipeline();
+        self.record_image(command_buffer, additive_pipeline, texture, 1, from);
+        self.record_image(command_buffer, additive_pipeline, shown, 0, to);
+
+        let viewport = self.viewport();
+        let background = self.clear_color();
+        self.record_overlay(
+            command_buffer,
+            self.engine.underlay_pipeline(),
+            &OverlayPushConstants {
+                rect: [0.0, 0.0, viewport.x, viewport.y],
+                fill: background,
+                border: background,
+                viewport: viewport.to_array(),
+                border_width: 0.0,
+            },
+        );
+    }
+
+    /// Draws each compared image into its pane, then the lines between them.
+    fn record_compare(&self, command_buffer: vk::CommandBuffer) {
+        let compare = self.compare.as_ref().unwrap();
+        let viewport = self.viewport();
+        let paths = self.compared_paths();
+        let panes = compare.panes(paths.len(), viewport);
+
+        for (i, (pane, path)) in (0..).zip(panes.iter().zip(&paths)) {
+            // the first pane may still show the previous image while loading
+            let texture = if i == 0 {
+                self.shown_texture()
+            } else {
+                self.frame_texture(path, self.playback.frame())
+            };
+            let (min, max) = pane.scissor;
+            let Some(texture) = texture.filter(|_| min.x < max.x && min.y < max.y) else {
+                continue;
+            };
+            self.set_viewport(command_buffer, pane.viewport, pane.scissor);
+            self.record_image(
+                command_buffer,
+                self.engine.graphics_pipeline(),
+                texture,
+                i,
+                Layer::OPAQUE,
+            );
+        }
+        let window = (Vec2::ZERO, viewport);
+        self.set_viewport(command_buffer, window, window);
+
+        let [r, g, b] = COMPARE_DIVIDER_COLOR;
+        for (min, max) in compare.dividers(paths.len(), viewport) {
+            self.record_overlay(
+                command_buffer,
+                self.engine.overlay_pipeline(),
+                &OverlayPushConstants {
+                    rect: [min.x, min.y, max.x, max.y],
+                    fill: [r, g, b, 1.0],
+                    border: [r, g, b, 1.0],
+                    viewport: viewport.to_array(),
+                    border_width: 0.0,
+                },
+            );
+        }
+    }
+
+    /// Maps clip space to `viewport` and draws only inside `scissor`, both
+    /// minimum and maximum corners in window pixels.
+    fn set_viewport(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        viewport: (Vec2, Vec2),
+        scissor: (Vec2, Vec2),
+    ) {
+        let device = self.engine.device();
+        let (min, max) = viewport;
+        let scissor_min = scissor.0.round().max(Vec2::ZERO);
+        let scissor_size = (scissor.1.round() - scissor_min).max(Vec2::ZERO);
+
+        unsafe {
+            device.cmd_set_viewport(
+                command_buffer,
+                0,
+                &[vk::Viewport::default()
+                    .x(min.x)
+                    .y(min.y)
+                    .width(max.x - min.x)
+                    .height(max.y - min.y)
+                    .min_depth(0.0)
+                    .max_depth(1.0)],
+            );
+            device.cmd_set_scissor(
+                command_buffer,
+                0,
+                &[vk::Rect2D {
+                    offset: vk::Offset2D {
+                        x: scissor_min.x as i32,
+                        y: scissor_min.y as i32,
+                    },
+                    extent: vk::Extent2D {
+                        width: scissor_size.x as u32,
+                        height: scissor_size.y as u32,
+                    },
+                }],
+            );
+        }
+    }
+
+    /// Draws a layer of `texture` one tile at a time, placed by the uniform
+    /// buffer's `transform`.
+    fn record_image(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: &Pipeline,
+        (texture, texture_layer): (&TiledTexture, u32),
+        transform: u32,
+        layer: Layer,
+    ) {
+        let device = self.engine.device();
+        let mesh = texture.mesh();
+
+        let layout = pipeline.layout();
+        let pipeline = pipeline.pipeline();
+
+        unsafe {
             device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
-            device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer.buffer()], &[0]);
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &[mesh.buffer()], &[0]);
             device.cmd_bind_index_buffer(
                 command_buffer,
-                index_buffer.buffer(),
-                0,
+                mesh.buffer(),
+                mesh.index_offset(),
                 vk::IndexType::UINT32,
             );
-            device.cmd_set_viewport(command_buffer, 0, &[vk::Viewport::default()
-                .x(0.0)
-                .y(0.0)
-                .width(swapchain.extent().width as f32)
-                .height(swapchain.extent().height as f32)
-                .min_depth(0.0)
-                .max_depth(1.0)]);
-            device.cmd_set_scissor(command_buffer, 0, &[vk::Rect2D::default()
-                .offset(vk::Offset2D::default().x(0).y(0))
-                .extent(swapchain.extent())]);
-
             device.cmd_bind_descriptor_sets(
                 command_buffer,
                 vk::PipelineBindPoint::GRAPHICS,
                 layout,
                 0,
-                &[engine.descriptor_set()],
+                &[self.engine.descriptor_set()],
                 &[],
             );
 
-            device.cmd_draw_indexed(
+            let pixel_art = self.settings.pixel_art;
+            let pixel_grid =
+                pixel_art && self.settings.pixel_grid && self.camera.zoom() >= PIXEL_GRID_MIN_ZOOM;
+            let push_constants = PushConstants {
+                scale_filter: self.settings.scale_filter as i32,
+                pixel_art: pixel_art.into(),
+                pixel_grid: pixel_grid.into(),
+                background: self.settings.background as i32,
+                checker_size: self.settings.checker_size as f32,
+                transform,
+                opacity: layer.opacity,
+                offset: layer.offset,
+                layer: texture_layer,
+            };
+            device.cmd_push_constants(
                 command_buffer,
-                INDICES.len().try_into().unwrap(),
-                1,
+                layout,
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
                 0,
+                push_constants.as_bytes(),
+            );
+
+            let index_count: u32 = QUAD_INDICES.len().try_into().unwrap();
+            for (i, tile) in (0..).zip(texture.tiles()) {
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    layout,
+                    1,
+                    &[tile.descriptor_set()],
+                    &[],
+                );
+                device.cmd_draw_indexed(command_buffer, index_count, 1, i * index_count, 0, 0);
+            }
+        }
+    }
+
+    /// Starts uploading thumbnails that arrived, records copies of those
+    /// already uploaded into the atlas, asks for the visible ones still
+    /// missing, and fills this frame's instance buffer with the rest. Returns
+    /// how many thumbnails to draw.
+    fn prepare_gallery(&mut self, command_buffer: vk::CommandBuffer) -> u32 {
+        let len = self.playlist.len();
+        let viewport = self.viewport();
+        let gallery = self.gallery.as_mut().unwrap();
+        let atlas = self.atlas.as_mut().unwrap();
+        gallery.scroll_by(0.0, len, viewport);
+        if !atlas.is_cleared() {
+            self.engine.record_atlas_clear(command_buffer, atlas);
+        }
+        let visible = gallery.visible(len, viewport);
+        // thumbnails on screen never make way for the ones arriving
+        let pinned: HashSet<&Path> = visible.clone().map(|i| self.playlist.get(i)).collect();
+
+        let ready = mem::take(&mut self.thumbnails);
+        if !ready.is_empty() {
+            let images: Vec<_> = ready.iter().map(|(_, image)| image).collect();
+            match self.engine.begin_thumbnail_upload(&images) {
+                Ok(upload) => self.thumbnail_uploads.push(PendingThumbnails {
+                    paths: ready.into_iter().map(|(path, _)| Some(path)).collect(),
+                    upload,
+                }),
+                Err(e) => eprintln!("Failed to upload thumbnails: {e}"),
+            }
+        }
+
+        let (done, pending): (Vec<_>, Vec<_>) = mem::take(&mut self.thumbnail_uploads)
+            .into_iter()
+            .partition(|x| self.engine.is_thumbnail_upload_complete(&x.upload));
+        self.thumbnail_uploads = pending;
+        for PendingThumbnails { paths, upload } in done {
+            let offsets: Vec<_> = paths
+                .into_iter()
+                .zip(upload.sizes())
+                .map(|(path, &size)| atlas.insert(path?, size, |x| pinned.contains(x.as_path())))
+                .collect();
+            self.engine
+                .record_thumbnail_copies(command_buffer, atlas, upload, &offsets);
+        }
+        // thumbnails still uploading aren't asked for again
+        let uploading: HashSet<&Path> = self
+            .thumbnail_uploads
+            .iter()
+            .flat_map(|x| x.paths.iter().flatten())
+            .map(PathBuf::as_path)
+            .collect();
+
+        let mut instances = Vec::new();
+        let mut missing = Vec::new();
+        for index in visible {
+            let path = self.playlist.get(index);
+            let Some((uv_rect, size)) = atlas.uv_rect(path) else {
+                if !self.thumbnail_errors.contains(path) && !uploading.contains(path) {
+                    missing.push(path.to_path_buf());
+                }
+                continue;
+            };
+
+            // centered in the cell, never enlarged
+            let (min, max) = gallery.cell_rect(index, viewport);
+            let size = size.as_vec2();
+            let size = size * (GALLERY_CELL_SIZE / size.max_element()).min(1.0);
+            let min = ((min + max - size) / 2.0).round();
+            let max = min + size;
+            instances.push(ThumbnailInstance {
+                rect: vec4(min.x, min.y, max.x, max.y),
+                uv_rect,
+            });
+        }
+        // only as many as fit without evicting what is on screen or on its way
+        let room = atlas
+            .slot_count()
+            .saturating_sub(instances.len() + uploading.len());
+        missing.truncate(room);
+        self.decoder.request_thumbnails(missing);
+
+        if let Err(e) = self.engine.reserve_instances(instances.len()) {
+            eprintln!("Failed to grow the instance buffer: {e}");
+        }
+        instances.truncate(self.engine.instance_capacity());
+        unsafe {
+            self.engine
+                .instance_buffer_ptr()
+                .copy_from(instances.as_ptr(), instances.len())
+        };
+        instances.len().try_into().unwrap()
+    }
+
+    /// Draws every visible thumbnail as an instance of the unit quad, then
+    /// outlines the selected one.
+    fn record_gallery(&self, command_buffer: vk::CommandBuffer, instance_count: u32) {
+        let device = self.engine.device();
+        let pipeline = self.engine.gallery_pipeline();
+        let quad = self.engine.unit_quad();
+        let atlas = self.atlas.as_ref().unwrap();
+        let gallery = self.gallery.as_ref().unwrap();
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.pipeline(),
+            );
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[quad.buffer(), self.engine.instance_buffer()],
+                &[0, 0],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                quad.buffer(),
+                quad.index_offset(),
+                vk::IndexType::UINT32,
+            );
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.layout(),
                 0,
+                &[self.engine.descriptor_set(), atlas.descriptor_set()],
+                &[],
+            );
+            let index_count: u32 = QUAD_INDICES.len().try_into().unwrap();
+            device.cmd_draw_indexed(command_buffer, index_count, instance_count, 0, 0, 0);
+        }
+
+        let viewport = self.viewport();
+        let (min, max) = gallery.cell_rect(gallery.selected(), viewport);
+        let [r, g, b] = GALLERY_SELECTION_COLOR;
+        self.record_overlay(
+            command_buffer,
+            self.engine.overlay_pipeline(),
+            &OverlayPushConstants {
+                rect: [min.x, min.y, max.x, max.y],
+                fill: [0.0; 4],
+                border: [r, g, b, 1.0],
+                viewport: viewport.to_array(),
+                border_width: GALLERY_SELECTION_BORDER,
+            },
+        );
+    }
+
+    /// Tints the window and outlines its edge while files hover over it.
+    fn record_drop_indicator(&self, command_buffer: vk::CommandBuffer) {
+        let viewport = self.viewport();
+
+        let [r, g, b] = DROP_INDICATOR_COLOR;
+        self.record_overlay(
+            command_buffer,
+            self.engine.overlay_pipeline(),
+            &OverlayPushConstants {
+                rect: [0.0, 0.0, viewport.x, viewport.y],
+                fill: [r, g, b, 1.0].map(|x| x * DROP_INDICATOR_OPACITY),
+                border: [r, g, b, 1.0],
+                viewport: viewport.to_array(),
+                border_width: DROP_INDICATOR_BORDER,
+            },
+        );
+    }
+
+    /// Fills and outlines a rectangle of the window.
+    fn record_overlay(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: &Pipeline,
+        push_constants: &OverlayPushConstants,
+    ) {
+        let device = self.engine.device();
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.pipeline(),
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
                 0,
+                push_constants.as_bytes(),
             );
-            device.cmd_end_render_pass(command_buffer);
-            device.end_command_buffer(command_buffer).unwrap();
+            device.cmd_draw(command_buffer, 4, 1, 0, 0);
+        }
+    }
+
+    fn viewport(&self) -> Vec2 {
+        let extent = self.engine.swapchain().extent();
+        vec2(extent.width as f32, extent.height as f32)
+    }
+
+    /// Size the shown image is fit into: a pane when comparing, otherwise
+    /// the window.
+    fn image_viewport(&self) -> Vec2 {
+        let viewport = self.viewport();
+        match &self.compare {
+            Some(compare) => compare.pane_size(self.compared_paths().len(), viewport),
+            None => viewport,
+        }
+    }
+
+    /// Cursor position within the pane under it, or the window.
+    fn pane_cursor(&self) -> Vec2 {
+        let Some(compare) = &self.compare else {
+            return self.cursor_position;
+        };
+        let count = self.compared_paths().len();
+        self.cursor_position - compare.pane_origin(self.cursor_position, count, self.viewport())
+    }
+
+    /// Images drawn at once, starting with the current one.
+    fn compared_paths(&self) -> Vec<PathBuf> {
+        let count = self
+            .compare
+            .as_ref()
+            .map_or(1, |x| x.image_count(self.playlist.len()));
+        (0..count as isize)
+            .map(|offset| self.playlist.peek(offset).to_path_buf())
+            .collect()
+    }
+
+    /// Images on screen, whose textures must not be evicted.
+    fn pinned_paths(&self) -> Vec<PathBuf> {
+        let mut paths = self.compared_paths();
+        paths.extend(self.shown.iter().cloned());
+        paths.extend(self.outgoing.as_ref().map(|x| x.path.clone()));
+        paths
+    }
+
+    /// Whether the cursor is close enough to the swipe divider to drag it.
+    fn is_over_divider(&self) -> bool {
+        match &self.compare {
+            Some(compare) if compare.mode() == CompareMode::Swipe && self.gallery.is_none() => {
+                let x = compare.divider_x(self.viewport());
+                (self.cursor_position.x - x).abs() <= DIVIDER_GRAB_DISTANCE
+            }
+            _ => false,
+        }
+    }
+
+    /// Texture and layer of the shown image, or of its current frame.
+    fn shown_texture(&self) -> Option<(&TiledTexture, u32)> {
+        self.frame_texture(self.shown.as_ref()?, self.playback.frame())
+    }
+
+    fn outgoing_texture(&self) -> Option<(&TiledTexture, u32)> {
+        let outgoing = self.outgoing.as_ref()?;
+        self.frame_texture(&outgoing.path, outgoing.frame)
+    }
+
+    /// Texture and layer of a frame of the image at `path`, or of its only
+    /// frame.
+    fn frame_texture(&self, path: &Path, frame: usize) -> Option<(&TiledTexture, u32)> {
+        Some(self.texture_cache.peek(path)?.frame(frame))
+    }
+
+    fn image_size(&self) -> Vec2 {
+        self.shown
+            .as_ref()
+            .and_then(|path| self.texture_cache.peek(path))
+            .map_or(Vec2::ONE, |cached| cached.image_size)
+    }
+
+    /// Size of the shown image once rotated.
+    fn displayed_size(&self) -> Vec2 {
+        self.orientation.apply_size(self.image_size())
+    }
+
+    fn image_transform(&self) -> Transform {
+        self.transform(self.image_size(), self.orientation)
+    }
+
+    /// Places an image of `image_size` laid out by `orientation` with the
+    /// camera, in a pane when comparing.
+    fn transform(&self, image_size: Vec2, orientation: Orientation) -> Transform {
+        let viewport = self.image_viewport();
+        let displayed_size = orientation.apply_size(image_size);
+        Transform {
+            model: Mat4::from_scale(vec3(image_size.x, image_size.y, 1.0)),
+            view: self.camera.view(displayed_size, viewport) * orientation.matrix(),
+            proj: Camera::projection(viewport),
         }
     }
 
     fn update_uniform_buffers(&mut self) {
         let engine = &self.engine;
-        let swapchain = engine.swapchain();
-        let start_time = self.start_time;
-
-        let time_elapsed = start_time.elapsed().unwrap().as_secs_f32();
-        let pi = f32::consts::PI;
-        let aspect_ratio: f32 = swapchain.extent().width as f32 / swapchain.extent().height as f32;
-
-        let ubo = UniformBufferObject {
-            model: Mat4::from_rotation_z(time_elapsed * pi / 2.0),
-            view: Mat4::look_at_rh(
-                vec3(2.0, 2.0, 2.0),
-                vec3(0.0, 0.0, 0.0),
-                vec3(0.0, 0.0, 1.0),
-            ),
-            proj: Mat4::perspective_rh(-pi / 4.0, aspect_ratio, 0.1, 10.0),
-        };
+        let mut transforms = [self.image_transform(); MAX_IMAGE_TRANSFORMS];
+        if let Some(outgoing) = &self.outgoing {
+            transforms[1] = outgoing.transform;
+        }
+        // the others share the camera of the first
+        if self.compare.is_some() {
+            for (transform, path) in transforms.iter_mut().zip(self.compared_paths()).skip(1) {
+                if let Some(cached) = self.texture_cache.peek(&path) {
+                    *transform = self.transform(cached.image_size, cached.orientation);
+                }
+            }
+        }
+
+        // the gallery covers the whole window
+        if self.gallery.is_some() {
+            transforms[0].proj = Camera::projection(self.viewport());
+        }
+
+        let ubo = UniformBufferObject { transforms };
 
         unsafe {
             engine
@@ -232,3 +2064,100 @@ impl App {
         };
     }
 }
+
+impl Drop for App {
+    fn drop(&mut self) {
+        for upload in self.uploads.drain(..).flat_map(|x| x.textures) {
+            let texture = self.engine.finish_texture_upload(upload);
+            self.engine.destroy_texture(texture);
+        }
+        for cached in self.texture_cache.drain() {
+            cached.destroy(&mut self.engine);
+        }
+        for pending in self.thumbnail_uploads.drain(..) {
+            self.engine.destroy_thumbnail_upload(pending.upload);
+        }
+        if let Some(atlas) = self.atlas.take() {
+            self.engine.destroy_thumbnail_atlas(atlas);
+        }
+    }
+}
+
+impl CachedTexture {
+    fn destroy(self, engine: &mut Engine) {
+        for texture in self.textures {
+            engine.destroy_texture(texture);
+        }
+    }
+
+    /// Device memory taken by every texture.
+    fn size(&self) -> vk::DeviceSize {
+        self.textures.iter().map(TiledTexture::size).sum()
+    }
+
+    fn frame_count(&self) -> usize {
+        self.textures.iter().map(|x| x.layer_count() as usize).sum()
+    }
+
+    /// Texture and layer holding `frame`, or the first frame when there is
+    /// no such frame.
+    fn frame(&self, frame: usize) -> (&TiledTexture, u32) {
+        let mut layer = frame;
+        for texture in &self.textures {
+            let layer_count = texture.layer_count() as usize;
+            if layer < layer_count {
+                return (texture, layer as u32);
+            }
+            layer -= layer_count;
+        }
+        (&self.textures[0], 0)
+    }
+
+    fn is_preview(&self) -> bool {
+        let extent = self.textures[0].extent();
+        vec2(extent.width as f32, extent.height as f32) != self.image_size
+    }
+}
+
+/// Starts uploading every frame, resized by `scale` when below one, into as
+/// few textures as the device's layer limit allows. Textures already on their
+/// way are dropped again if one of them fails.
+fn begin_frame_uploads(
+    engine: &mut Engine,
+    frames: &[&image::RgbaImage],
+    scale: f64,
+) -> Result<Vec<TextureUpload>, Box<dyn Error>> {
+    let downscaled: Vec<_> = if scale < 1.0 {
+        frames.iter().map(|x| downscale(x, scale)).collect()
+    } else {
+        Vec::new()
+    };
+    let frames: Vec<_> = if scale < 1.0 {
+        downscaled.iter().collect()
+    } else {
+        frames.to_vec()
+    };
+
+    let max_layers = engine.max_texture_layers() as usize;
+    let mut uploads = Vec::new();
+    for layers in frames.chunks(max_layers) {
+        match engine.begin_texture_upload(layers) {
+            Ok(upload) => uploads.push(upload),
+            Err(e) => {
+                for upload in uploads {
+                    let texture = engine.finish_texture_upload(upload);
+                    engine.destroy_texture(texture);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(uploads)
+}
+
+/// Resizes `image` by `scale`, keeping at least one pixel on each side.
+fn downscale(image: &image::RgbaImage, scale: f64) -> image::RgbaImage {
+    let width = ((image.width() as f64 * scale) as u32).max(1);
+    let height = ((image.height() as f64 * scale) as u32).max(1);
+    image::imageops::resize(image, width, height, FilterType::Triangle)
+}
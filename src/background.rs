@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// What shows through transparent parts of the image and around it. The
+/// discriminants are shared with the fragment shader.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[repr(i32)]
+pub enum Background {
+    /// Checkerboard behind the image, solid color around it.
+    #[default]
+    Checkerboard = 0,
+    Solid = 1,
+    /// See-through window, where the compositor supports it. Only available
+    /// when it is the background at startup.
+    Transparent = 2,
+}
+
+impl Background {
+    pub fn next(self) -> Self {
+        match self {
+            Background::Checkerboard => Background::Solid,
+            Background::Solid => Background::Transparent,
+            Background::Transparent => Background::Checkerboard,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use crate::action::Action;
+use std::{collections::HashMap, error::Error};
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, ModifiersState, NamedKey, SmolStr},
+};
+
+/// A key along with the modifiers held for it, written like `ctrl+shift+Left`.
+///
+/// Shift is folded into character keys, since it already changes the
+/// character: `shift+r` and `R` are the same chord.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    modifiers: ModifiersState,
+    key: Key,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// What each key chord, mouse button and wheel direction does.
+pub struct Bindings {
+    keys: HashMap<KeyChord, Action>,
+    mouse: HashMap<MouseButton, Action>,
+    wheel: HashMap<WheelDirection, Action>,
+}
+
+/// Names accepted for keys that don't type a character.
+const NAMED_KEYS: [(&str, NamedKey); 27] = [
+    ("left", NamedKey::ArrowLeft),
+    ("right", NamedKey::ArrowRight),
+    ("up", NamedKey::ArrowUp),
+    ("down", NamedKey::ArrowDown),
+    ("space", NamedKey::Space),
+    ("backspace", NamedKey::Backspace),
+    ("enter", NamedKey::Enter),
+    ("escape", NamedKey::Escape),
+    ("tab", NamedKey::Tab),
+    ("home", NamedKey::Home),
+    ("end", NamedKey::End),
+    ("pageup", NamedKey::PageUp),
+    ("pagedown", NamedKey::PageDown),
+    ("delete", NamedKey::Delete),
+    ("insert", NamedKey::Insert),
+    ("f1", NamedKey::F1),
+    ("f2", NamedKey::F2),
+    ("f3", NamedKey::F3),
+    ("f4", NamedKey::F4),
+    ("f5", NamedKey::F5),
+    ("f6", NamedKey::F6),
+    ("f7", NamedKey::F7),
+    ("f8", NamedKey::F8),
+    ("f9", NamedKey::F9),
+    ("f10", NamedKey::F10),
+    ("f11", NamedKey::F11),
+    ("f12", NamedKey::F12),
+];
+
+impl KeyChord {
+    /// The chord for a key pressed while `modifiers` are held.
+    pub fn new(key: Key, modifiers: ModifiersState) -> Self {
+        let modifiers = match key {
+            Key::Character(_) => modifiers - ModifiersState::SHIFT,
+            _ => modifiers,
+        };
+        Self { modifiers, key }
+    }
+
+    pub fn parse(chord: &str) -> Result<Self, Box<dyn Error>> {
+        // `+` is both the separator and a key
+        let (prefix, key) = if chord == "+" {
+            ("", "+")
+        } else if let Some(prefix) = chord.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            chord.rsplit_once('+').unwrap_or(("", chord))
+        };
+
+        let mut modifiers = ModifiersState::empty();
+        for name in prefix.split('+').filter(|x| !x.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" => ModifiersState::ALT,
+                "super" | "meta" => ModifiersState::SUPER,
+                _ => return Err(format!("unknown modifier `{name}`").into()),
+            };
+        }
+
+        let lowercase = key.to_lowercase();
+        let key = if let Some((_, named)) = NAMED_KEYS.iter().find(|(x, _)| *x == lowercase) {
+            Key::Named(*named)
+        } else if key.chars().count() == 1 {
+            if modifiers.contains(ModifiersState::SHIFT) {
+                // only letters have a shifted form known regardless of layout
+                let uppercase = key.to_uppercase();
+                if uppercase == lowercase {
+                    return Err(format!(
+                        "shift+`{key}` types another character, bind that one instead"
+                    )
+                    .into());
+                }
+                Key::Character(SmolStr::new(uppercase))
+            } else {
+                Key::Character(SmolStr::new(key))
+            }
+        } else {
+            return Err(format!("unknown key `{key}`").into());
+        };
+
+        Ok(Self::new(key, modifiers))
+    }
+}
+
+impl WheelDirection {
+    fn parse(name: &str) -> Result<Self, Box<dyn Error>> {
+        match name {
+            "up" => Ok(WheelDirection::Up),
+            "down" => Ok(WheelDirection::Down),
+            "left" => Ok(WheelDirection::Left),
+            "right" => Ok(WheelDirection::Right),
+            _ => Err(format!("unknown wheel direction `{name}`").into()),
+        }
+    }
+}
+
+fn parse_mouse_button(name: &str) -> Result<MouseButton, Box<dyn Error>> {
+    match name {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        "back" => Ok(MouseButton::Back),
+        "forward" => Ok(MouseButton::Forward),
+        _ => Err(format!("unknown mouse button `{name}`").into()),
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keys = [
+            ("Right", Action::Next),
+            ("Space", Action::Next),
+            ("Left", Action::Prev),
+            ("Backspace", Action::Prev),
+            ("PageDown", Action::NextPage),
+            ("PageUp", Action::PrevPage),
+            ("Home", Action::First),
+            ("End", Action::Last),
+            ("+", Action::ZoomIn),
+            ("=", Action::ZoomIn),
+            ("-", Action::ZoomOut),
+            ("0", Action::ResetZoom),
+            ("f", Action::Fit),
+            ("c", Action::Fill),
+            ("1", Action::ActualSize),
+            ("w", Action::FitWidth),
+            ("h", Action::FitHeight),
+            ("r", Action::RotateCw),
+            ("R", Action::RotateCcw),
+            ("x", Action::FlipHorizontal),
+            ("y", Action::FlipVertical),
+            ("m", Action::CycleScaleFilter),
+            ("p", Action::TogglePixelArt),
+            ("g", Action::TogglePixelGrid),
+            ("b", Action::CycleBackground),
+            ("[", Action::SmallerCheckers),
+            ("]", Action::LargerCheckers),
+            ("a", Action::ToggleFitWindow),
+            ("F11", Action::ToggleFullscreen),
+            ("Escape", Action::ExitFullscreen),
+            ("i", Action::MemoryStats),
+            ("k", Action::TogglePlayback),
+            (".", Action::NextFrame),
+            (",", Action::PrevFrame),
+            (">", Action::FasterPlayback),
+            ("<", Action::SlowerPlayback),
+            ("s", Action::ToggleSlideshow),
+            ("t", Action::ToggleGallery),
+            ("v", Action::ToggleCompare),
+            ("V", Action::CycleCompareMode),
+            ("Enter", Action::Open),
+            ("Up", Action::PrevRow),
+            ("Down", Action::NextRow),
+            ("q", Action::Quit),
+        ]
+        .into_iter()
+        .map(|(chord, action)| (KeyChord::parse(chord).unwrap(), action))
+        .collect();
+
+        let mouse = HashMap::from([
+            (MouseButton::Left, Action::Pan),
+            (MouseButton::Middle, Action::ResetZoom),
+            (MouseButton::Back, Action::Prev),
+            (MouseButton::Forward, Action::Next),
+        ]);
+
+        let wheel = HashMap::from([
+            (WheelDirection::Up, Action::ZoomIn),
+            (WheelDirection::Down, Action::ZoomOut),
+        ]);
+
+        Self { keys, mouse, wheel }
+    }
+}
+
+impl Bindings {
+    /// Adds bindings from the config file on top of the current ones. Entries
+    /// that don't parse are skipped and described in the returned errors.
+    pub fn extend(
+        &mut self,
+        keys: HashMap<String, Action>,
+        mouse: HashMap<String, Action>,
+        wheel: HashMap<String, Action>,
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (chord, action) in keys {
+            match KeyChord::parse(&chord) {
+                Ok(chord) => _ = self.keys.insert(chord, action),
+                Err(e) => errors.push(format!("keys.\"{chord}\": {e}")),
+            }
+        }
+        for (button, action) in mouse {
+            match parse_mouse_button(&button) {
+                Ok(button) => _ = self.mouse.insert(button, action),
+                Err(e) => errors.push(format!("mouse.{button}: {e}")),
+            }
+        }
+        for (direction, action) in wheel {
+            match WheelDirection::parse(&direction) {
+                Ok(direction) => _ = self.wheel.insert(direction, action),
+                Err(e) => errors.push(format!("wheel.{direction}: {e}")),
+            }
+        }
+
+        errors
+    }
+
+    pub fn key(&self, chord: &KeyChord) -> Option<Action> {
+        self.keys.get(chord).copied()
+    }
+
+    pub fn mouse(&self, button: MouseButton) -> Option<Action> {
+        self.mouse.get(&button).copied()
+    }
+
+    pub fn wheel(&self, direction: WheelDirection) -> Option<Action> {
+        self.wheel.get(&direction).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(key: Key, modifiers: ModifiersState) -> KeyChord {
+        KeyChord { modifiers, key }
+    }
+
+    fn character(x: &str) -> Key {
+        Key::Character(SmolStr::new(x))
+    }
+
+    #[test]
+    fn plus_alone_is_the_plus_key() {
+        assert_eq!(
+            KeyChord::parse("+").unwrap(),
+            chord(character("+"), ModifiersState::empty())
+        );
+    }
+
+    #[test]
+    fn plus_after_a_modifier_is_the_plus_key() {
+        assert_eq!(
+            KeyChord::parse("ctrl++").unwrap(),
+            chord(character("+"), ModifiersState::CONTROL)
+        );
+    }
+
+    #[test]
+    fn modifiers_are_case_insensitive_and_combine() {
+        assert_eq!(
+            KeyChord::parse("Ctrl+ALT+Left").unwrap(),
+            chord(
+                Key::Named(NamedKey::ArrowLeft),
+                ModifiersState::CONTROL | ModifiersState::ALT
+            )
+        );
+    }
+
+    #[test]
+    fn shift_with_a_letter_is_the_uppercase_letter() {
+        let shifted = KeyChord::parse("shift+r").unwrap();
+
+        assert_eq!(shifted, KeyChord::parse("R").unwrap());
+        assert_eq!(shifted, chord(character("R"), ModifiersState::empty()));
+    }
+
+    #[test]
+    fn shift_with_a_named_key_stays_a_modifier() {
+        assert_eq!(
+            KeyChord::parse("shift+Space").unwrap(),
+            chord(Key::Named(NamedKey::Space), ModifiersState::SHIFT)
+        );
+    }
+
+    #[test]
+    fn shift_with_a_non_letter_is_rejected() {
+        assert!(KeyChord::parse("shift+1").is_err());
+        assert!(KeyChord::parse("shift+-").is_err());
+    }
+
+    #[test]
+    fn unknown_modifiers_and_keys_are_rejected() {
+        assert!(KeyChord::parse("hyper+a").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use ash::prelude::VkResult;
+use crate::allocator::{Allocation, Allocator};
 use ash::vk;
 use std::error::Error;
 use std::ffi::c_void;
@@ -10,44 +10,37 @@ pub enum BufferCreationError {
 
 #[derive(Debug)]
 pub struct Buffer {
-    size: vk::DeviceSize,
     buffer: vk::Buffer,
-    memory: vk::DeviceMemory,
-    ptr: Option<*mut c_void>,
+    allocation: Allocation,
 }
 
 impl Buffer {
     pub fn new(
         device: &ash::Device,
+        allocator: &mut Allocator,
         buffer_info: &vk::BufferCreateInfo,
         allocation_callbacks: Option<&vk::AllocationCallbacks>,
         mem_props: vk::MemoryPropertyFlags,
-        device_mem_props: vk::PhysicalDeviceMemoryProperties,
     ) -> Result<Self, Box<dyn Error>> {
-        let size = buffer_info.size;
         let buffer = unsafe { device.create_buffer(buffer_info, allocation_callbacks)? };
         let mem_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
 
-        let alloc_info = vk::MemoryAllocateInfo::default()
-            .allocation_size(mem_requirements.size)
-            .memory_type_index(
-                Self::find_memory_type_index(
-                    device_mem_props,
-                    mem_requirements.memory_type_bits,
-                    mem_props,
-                )?
-                .try_into()?,
-            );
-
-        let memory = unsafe { device.allocate_memory(&alloc_info, allocation_callbacks)? };
-        unsafe { device.bind_buffer_memory(buffer, memory, 0)? };
+        let allocation = match allocator.allocate(
+            device,
+            mem_requirements,
+            mem_props,
+            true,
+            allocation_callbacks,
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                unsafe { device.destroy_buffer(buffer, allocation_callbacks) };
+                return Err(e);
+            }
+        };
+        unsafe { device.bind_buffer_memory(buffer, allocation.memory(), allocation.offset())? };
 
-        Ok(Self {
-            size,
-            buffer,
-            memory,
-            ptr: None,
-        })
+        Ok(Self { buffer, allocation })
     }
 
     pub fn find_memory_type_index(
@@ -66,44 +59,30 @@ impl Buffer {
         Err(BufferCreationError::MemoryTypeNotFound)
     }
 
-    pub fn map_memory(
-        &mut self,
-        device: &ash::Device,
-        offset: vk::DeviceSize,
-        flags: vk::MemoryMapFlags,
-    ) -> VkResult<()> {
-        self.ptr = unsafe { Some(device.map_memory(self.memory(), offset, self.size, flags)?) };
-        Ok(())
-    }
-
-    pub fn unmap_memory(&mut self, device: &ash::Device) {
-        self.ptr = None;
-        unsafe {
-            device.unmap_memory(self.memory());
-        }
-    }
-
     pub fn cleanup(
         self,
         device: &ash::Device,
+        allocator: &mut Allocator,
         allocation_callbacks: Option<&vk::AllocationCallbacks>,
     ) {
         unsafe {
             device.destroy_buffer(self.buffer(), allocation_callbacks);
-            device.free_memory(self.memory(), allocation_callbacks);
         }
-    }
-
-    pub fn memory(&self) -> vk::DeviceMemory {
-        self.memory
+        allocator.free(device, self.allocation, allocation_callbacks);
     }
 
     pub fn buffer(&self) -> vk::Buffer {
         self.buffer
     }
 
+    /// Size of the memory backing the buffer, at least what was asked for.
+    pub fn size(&self) -> vk::DeviceSize {
+        self.allocation.size()
+    }
+
+    /// Persistently mapped pointer, for buffers in host visible memory.
     pub fn ptr(&self) -> Option<*mut c_void> {
-        self.ptr
+        self.allocation.ptr()
     }
 }
 
@@ -0,0 +1,140 @@
+use crate::{
+    constants::{MAX_ZOOM, MIN_ZOOM},
+    fit_mode::FitMode,
+};
+use glam::{Mat4, Vec2, vec3};
+
+/// 2D view over an image, in window pixels with the origin at the top left.
+///
+/// `pan` is the offset of the image center from the window center, so an
+/// untouched camera keeps the image centered while the window is resized.
+pub struct Camera {
+    pan: Vec2,
+    zoom: f32,
+    fit_mode: FitMode,
+    /// Cleared by manual pan/zoom so resizing doesn't undo the user's view.
+    follow_fit: bool,
+    /// Keeps image pixels square: zoom is a whole factor (or a whole fraction
+    /// below 1) and the image's corner sits on a window pixel.
+    pixel_snap: bool,
+}
+
+impl Camera {
+    pub fn new(fit_mode: FitMode, pixel_snap: bool) -> Self {
+        Self {
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            fit_mode,
+            follow_fit: true,
+            pixel_snap,
+        }
+    }
+
+    pub fn set_pixel_snap(&mut self, pixel_snap: bool, image_size: Vec2, viewport: Vec2) {
+        self.pixel_snap = pixel_snap;
+        if self.follow_fit {
+            self.fit(image_size, viewport);
+        } else if pixel_snap {
+            self.zoom_at(1.0, viewport / 2.0, viewport);
+        }
+    }
+
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode, image_size: Vec2, viewport: Vec2) {
+        self.fit_mode = fit_mode;
+        self.follow_fit = true;
+        self.fit(image_size, viewport);
+    }
+
+    /// Re-applies the fit mode unless the user has moved the view since.
+    pub fn update_fit(&mut self, image_size: Vec2, viewport: Vec2) {
+        if self.follow_fit {
+            self.fit(image_size, viewport);
+        }
+    }
+
+    fn fit(&mut self, image_size: Vec2, viewport: Vec2) {
+        let zoom = self
+            .fit_mode
+            .zoom(image_size, viewport)
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        // round towards whichever side keeps the fit's promise
+        self.zoom = match self.fit_mode {
+            _ if !self.pixel_snap => zoom,
+            FitMode::Fill => snap_up(zoom),
+            _ => snap_down(zoom),
+        };
+
+        // start at the top/left edge of an image that overflows along the fitted axis
+        let overflow = (image_size * self.zoom - viewport).max(Vec2::ZERO) / 2.0;
+        self.pan = match self.fit_mode {
+            FitMode::FitWidth => Vec2::new(0.0, overflow.y),
+            FitMode::FitHeight => Vec2::new(overflow.x, 0.0),
+            _ => Vec2::ZERO,
+        };
+    }
+
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.pan += delta;
+        self.follow_fit = false;
+    }
+
+    /// Multiplies the zoom by `factor` while keeping the image point under
+    /// `anchor` (in window pixels) fixed on screen. When snapping, the zoom
+    /// moves to the next whole factor in that direction instead.
+    pub fn zoom_at(&mut self, factor: f32, anchor: Vec2, viewport: Vec2) {
+        let new_zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let new_zoom = match factor {
+            _ if !self.pixel_snap => new_zoom,
+            f if f > 1.0 => snap_up(new_zoom),
+            _ => snap_down(new_zoom),
+        };
+        let from_center = anchor - viewport / 2.0;
+
+        self.pan = from_center - (from_center - self.pan) * (new_zoom / self.zoom);
+        self.zoom = new_zoom;
+        self.follow_fit = false;
+    }
+
+    pub fn fit_mode(&self) -> FitMode {
+        self.fit_mode
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Maps image-centered pixels to window pixels.
+    pub fn view(&self, image_size: Vec2, viewport: Vec2) -> Mat4 {
+        let mut center = viewport / 2.0 + self.pan;
+        if self.pixel_snap {
+            let half_size = image_size * self.zoom / 2.0;
+            center = (center - half_size).round() + half_size;
+        }
+
+        Mat4::from_translation(center.extend(0.0))
+            * Mat4::from_scale(vec3(self.zoom, self.zoom, 1.0))
+    }
+
+    /// Maps window pixels to clip space, y pointing down like Vulkan's.
+    pub fn projection(viewport: Vec2) -> Mat4 {
+        Mat4::orthographic_rh(0.0, viewport.x, 0.0, viewport.y, -1.0, 1.0)
+    }
+}
+
+/// Smallest whole zoom factor, or whole fraction when zoomed out, not below `zoom`.
+fn snap_up(zoom: f32) -> f32 {
+    if zoom >= 1.0 {
+        zoom.ceil()
+    } else {
+        1.0 / (1.0 / zoom).floor()
+    }
+}
+
+/// Largest whole zoom factor, or whole fraction when zoomed out, not above `zoom`.
+fn snap_down(zoom: f32) -> f32 {
+    if zoom >= 1.0 {
+        zoom.floor()
+    } else {
+        1.0 / (1.0 / zoom).ceil()
+    }
+}
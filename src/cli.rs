@@ -0,0 +1,202 @@
+use crate::{
+    compare::CompareMode, constants::MAX_COMPARED_IMAGES, fit_mode::FitMode,
+    gpu_selector::GpuSelector, present_mode::PresentMode, settings::Settings,
+    transition::Transition,
+};
+use serde::{
+    Deserialize,
+    de::value::{Error as ValueError, StrDeserializer},
+};
+use std::{ffi::OsString, path::PathBuf};
+
+pub const USAGE: &str = "\
+Usage: image-viewer [OPTIONS] <PATH>...
+       image-viewer [OPTIONS] --watch-fifo <PATH>
+
+Shows images, browsing the directory of a single file or every image given.
+
+Arguments:
+  <PATH>...                  Image files or directories, - for standard input
+
+Options:
+  -r, --recursive            Include images in subdirectories
+  -f, --fullscreen           Start in borderless fullscreen
+      --fit <MODE>           fit, fill, actual-size, fit-width or fit-height
+      --zoom <FACTOR>        Initial zoom, like 2 or 150%
+      --start-at <IMAGE>     File name or 1-based position to open first
+      --slideshow <SECONDS>  Advance to the next image at this interval
+      --transition <KIND>    none, crossfade or slide between slideshow images
+      --shuffle              Randomize the order of the images
+      --compare[=<COUNT>]    Show 2 to 4 images side by side with the same pan
+                             and zoom, by default as many as configured
+      --swipe                Show two images over each other, split by a
+                             draggable divider
+      --gpu <INDEX|NAME>     Physical device to render with
+      --present-mode <MODE>  fifo, fifo-relaxed, mailbox or immediate
+      --watch-fifo <PATH>    Show each image written to a named pipe, one
+                             image per writer, instead of any paths
+      --config <FILE>        Config file to read instead of the default
+  -h, --help                 Print this help
+  -V, --version              Print the version";
+
+/// Options from the command line. Those that are also settings override the
+/// config file.
+#[derive(Debug, Default)]
+pub struct Cli {
+    pub paths: Vec<PathBuf>,
+    pub recursive: bool,
+    pub fullscreen: bool,
+    pub fit: Option<FitMode>,
+    pub zoom: Option<f32>,
+    pub start_at: Option<String>,
+    pub slideshow: Option<f64>,
+    pub transition: Option<Transition>,
+    pub shuffle: bool,
+    pub compare: bool,
+    pub compare_count: Option<usize>,
+    pub swipe: bool,
+    pub gpu: Option<GpuSelector>,
+    pub present_mode: Option<PresentMode>,
+    pub watch_fifo: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+}
+
+pub enum Command {
+    Run(Cli),
+    Help,
+    Version,
+}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Command, String> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        let mut only_paths = false;
+
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.to_str().filter(|x| x.starts_with('-') && !only_paths) else {
+                cli.paths.push(arg.into());
+                continue;
+            };
+            // accept --flag=value as well as --flag value
+            let (flag, inline_value) = match flag.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_owned())),
+                _ => (flag, None),
+            };
+            let mut value = || -> Result<String, String> {
+                match inline_value.clone() {
+                    Some(value) => Ok(value),
+                    None => args
+                        .next()
+                        .and_then(|x| x.into_string().ok())
+                        .ok_or_else(|| format!("{flag} needs a value")),
+                }
+            };
+
+            match flag {
+                "--" => only_paths = true,
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-r" | "--recursive" => cli.recursive = true,
+                "-f" | "--fullscreen" => cli.fullscreen = true,
+                "--shuffle" => cli.shuffle = true,
+                "--compare" => {
+                    cli.compare = true;
+                    // only inline, so a path after the flag stays a path
+                    if let Some(count) = &inline_value {
+                        cli.compare_count = Some(parse_compare_count(count)?);
+                    }
+                }
+                "--swipe" => cli.swipe = true,
+                "--fit" => cli.fit = Some(parse_enum(flag, &value()?)?),
+                "--present-mode" => cli.present_mode = Some(parse_enum(flag, &value()?)?),
+                "--transition" => cli.transition = Some(parse_enum(flag, &value()?)?),
+                "--zoom" => cli.zoom = Some(parse_zoom(&value()?)?),
+                "--start-at" => cli.start_at = Some(value()?),
+                "--slideshow" => {
+                    let seconds = value()?;
+                    match seconds.parse::<f64>() {
+                        Ok(x) if x > 0.0 && x.is_finite() => cli.slideshow = Some(x),
+                        _ => return Err(format!("invalid interval for --slideshow: `{seconds}`")),
+                    }
+                }
+                "--gpu" => cli.gpu = Some(GpuSelector::parse(&value()?)),
+                "--watch-fifo" => cli.watch_fifo = Some(value()?.into()),
+                "--config" => cli.config = Some(value()?.into()),
+                // a lone dash is a path, left for reading stdin
+                "-" => cli.paths.push(arg.into()),
+                _ => return Err(format!("unknown option `{flag}`")),
+            }
+        }
+
+        match (&cli.watch_fifo, cli.paths.is_empty()) {
+            (Some(_), false) => return Err("--watch-fifo takes no other paths".to_owned()),
+            (None, true) => return Err("no image given".to_owned()),
+            _ => (),
+        }
+        Ok(Command::Run(cli))
+    }
+
+    /// Overrides `settings` with whatever was given on the command line.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.recursive |= self.recursive;
+        settings.fullscreen |= self.fullscreen;
+        if let Some(fit) = self.fit {
+            settings.fit_mode = fit;
+        }
+        if self.zoom.is_some() {
+            settings.zoom = self.zoom;
+        }
+        if let Some(interval) = self.slideshow {
+            settings.slideshow = true;
+            settings.slideshow_interval = interval;
+        }
+        if let Some(transition) = self.transition {
+            settings.transition = transition;
+        }
+        if self.compare {
+            settings.compare = true;
+            settings.compare_mode = CompareMode::Split;
+            if let Some(count) = self.compare_count {
+                settings.compare_count = count;
+            }
+        }
+        if self.swipe {
+            settings.compare = true;
+            settings.compare_mode = CompareMode::Swipe;
+        }
+        if self.gpu.is_some() {
+            settings.gpu = self.gpu.clone();
+        }
+        if let Some(present_mode) = self.present_mode {
+            settings.present_mode = present_mode;
+        }
+    }
+}
+
+/// Parses a kebab-case value the same way the config file does.
+fn parse_enum<'a, T: Deserialize<'a>>(flag: &str, value: &'a str) -> Result<T, String> {
+    T::deserialize(StrDeserializer::<ValueError>::new(value))
+        .map_err(|e| format!("invalid value for {flag}: {e}"))
+}
+
+fn parse_compare_count(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(x) if (2..=MAX_COMPARED_IMAGES).contains(&x) => Ok(x),
+        _ => Err(format!(
+            "invalid count for --compare: `{value}`, expected 2 to {MAX_COMPARED_IMAGES}"
+        )),
+    }
+}
+
+fn parse_zoom(value: &str) -> Result<f32, String> {
+    let zoom = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().map(|x| x / 100.0),
+        None => value.parse(),
+    };
+    match zoom {
+        Ok(x) if x > 0.0 => Ok(x),
+        _ => Err(format!("invalid value for --zoom: `{value}`")),
+    }
+}
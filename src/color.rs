@@ -0,0 +1,54 @@
+use image::RgbaImage;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// How the color channels of decoded images relate to their alpha.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlphaMode {
+    /// Color is independent of alpha, as in PNG, GIF and WebP.
+    #[default]
+    Straight,
+    /// Color was already multiplied by alpha in sRGB space, as some TIFF and
+    /// EXR writers do.
+    Premultiplied,
+}
+
+static SRGB_TO_LINEAR: LazyLock<[f32; 256]> =
+    LazyLock::new(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)));
+
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts `image` to alpha premultiplied in linear light and stored sRGB
+/// encoded. Sampling the sRGB texture then decodes to premultiplied linear
+/// color, which filters and blends without dark fringes.
+pub fn premultiply(image: &mut RgbaImage, source: AlphaMode) {
+    // opaque pixels are the same either way
+    for pixel in image.pixels_mut().filter(|x| x[3] < u8::MAX) {
+        let alpha = pixel[3] as f32 / 255.0;
+        for channel in &mut pixel.0[..3] {
+            let straight = match source {
+                AlphaMode::Straight => SRGB_TO_LINEAR[*channel as usize],
+                AlphaMode::Premultiplied if alpha > 0.0 => {
+                    srgb_to_linear((*channel as f32 / 255.0 / alpha).min(1.0))
+                }
+                AlphaMode::Premultiplied => 0.0,
+            };
+            *channel = (linear_to_srgb(straight * alpha) * 255.0).round() as u8;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::constants::{COMPARE_DIVIDER_WIDTH, MAX_COMPARED_IMAGES};
+use glam::{Vec2, vec2};
+use serde::Deserialize;
+
+/// How compared images share the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareMode {
+    /// Side by side, or two by two for four images.
+    #[default]
+    Split,
+    /// Two images over each other, the first left of a divider and the
+    /// second right of it.
+    Swipe,
+}
+
+/// Layout of the images compared with the current one, which all share its
+/// pan and zoom.
+#[derive(Debug)]
+pub struct Compare {
+    mode: CompareMode,
+    /// Images compared side by side, the current one included.
+    count: usize,
+    /// Swipe divider as a fraction of the window width.
+    divider: f32,
+}
+
+/// Where one image is drawn: the area it is laid out in, and the part of the
+/// window it may cover. Both are minimum and maximum corners in window pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct Pane {
+    pub viewport: (Vec2, Vec2),
+    pub scissor: (Vec2, Vec2),
+}
+
+impl CompareMode {
+    pub fn next(self) -> Self {
+        match self {
+            CompareMode::Split => CompareMode::Swipe,
+            CompareMode::Swipe => CompareMode::Split,
+        }
+    }
+}
+
+impl Compare {
+    pub fn new(mode: CompareMode, count: usize) -> Self {
+        Self {
+            mode,
+            count: count.clamp(2, MAX_COMPARED_IMAGES),
+            divider: 0.5,
+        }
+    }
+
+    pub fn mode(&self) -> CompareMode {
+        self.mode
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    /// Images shown at once out of a playlist of `len`.
+    pub fn image_count(&self, len: usize) -> usize {
+        match self.mode {
+            CompareMode::Split => self.count.min(len),
+            CompareMode::Swipe => len.min(2),
+        }
+    }
+
+    /// Size every image is fit into, the same for all so they line up.
+    pub fn pane_size(&self, count: usize, viewport: Vec2) -> Vec2 {
+        match self.mode {
+            CompareMode::Split => (viewport / grid(count)).floor().max(Vec2::ONE),
+            CompareMode::Swipe => viewport,
+        }
+    }
+
+    /// Where each of `count` images goes.
+    pub fn panes(&self, count: usize, viewport: Vec2) -> Vec<Pane> {
+        match self.mode {
+            CompareMode::Split => (0..count)
+                .map(|i| {
+                    let rect = self.cell_rect(i, count, viewport);
+                    Pane {
+                        viewport: rect,
+                        scissor: rect,
+                    }
+                })
+                .collect(),
+            CompareMode::Swipe => {
+                let divider = self.divider_x(viewport);
+                let window = (Vec2::ZERO, viewport);
+                [
+                    (Vec2::ZERO, vec2(divider, viewport.y)),
+                    (vec2(divider, 0.0), viewport),
+                ]
+                .into_iter()
+                .take(count)
+                .map(|scissor| Pane {
+                    viewport: window,
+                    scissor,
+                })
+                .collect()
+            }
+        }
+    }
+
+    /// Top left corner of the pane under `position`, to zoom around the same
+    /// point of every image.
+    pub fn pane_origin(&self, position: Vec2, count: usize, viewport: Vec2) -> Vec2 {
+        if self.mode == CompareMode::Swipe {
+            return Vec2::ZERO;
+        }
+        let size = self.pane_size(count, viewport);
+        let cell = (position / size)
+            .floor()
+            .clamp(Vec2::ZERO, grid(count) - 1.0);
+        cell * size
+    }
+
+    /// Lines drawn between the panes, as minimum and maximum corners.
+    pub fn dividers(&self, count: usize, viewport: Vec2) -> Vec<(Vec2, Vec2)> {
+        let half_width = COMPARE_DIVIDER_WIDTH / 2.0;
+        let vertical = |x: f32| (vec2(x - half_width, 0.0), vec2(x + half_width, viewport.y));
+        let horizontal = |y: f32| (vec2(0.0, y - half_width), vec2(viewport.x, y + half_width));
+
+        match self.mode {
+            CompareMode::Split => {
+                let grid = grid(count);
+                let size = self.pane_size(count, viewport);
+                let columns = (1..grid.x as usize).map(|i| vertical(i as f32 * size.x));
+                let rows = (1..grid.y as usize).map(|i| horizontal(i as f32 * size.y));
+                columns.chain(rows).collect()
+            }
+            CompareMode::Swipe if count > 1 => vec![vertical(self.divider_x(viewport))],
+            CompareMode::Swipe => Vec::new(),
+        }
+    }
+
+    pub fn divider_x(&self, viewport: Vec2) -> f32 {
+        (self.divider * viewport.x).round()
+    }
+
+    /// Moves the swipe divider to `x` window pixels.
+    pub fn set_divider(&mut self, x: f32, viewport: Vec2) {
+        self.divider = (x / viewport.x).clamp(0.0, 1.0);
+    }
+
+    fn cell_rect(&self, index: usize, count: usize, viewport: Vec2) -> (Vec2, Vec2) {
+        let columns = grid(count).x as usize;
+        let size = self.pane_size(count, viewport);
+        let min = vec2((index % columns) as f32, (index / columns) as f32) * size;
+        (min, min + size)
+    }
+}
+
+/// Columns and rows of panes for `count` images side by side.
+fn grid(count: usize) -> Vec2 {
+    match count {
+        4 => vec2(2.0, 2.0),
+        _ => vec2(count.max(1) as f32, 1.0),
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{action::Action, bindings::Bindings, paths, settings::Settings};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Settings and input bindings from `config.toml`. Settings sit at the top
+/// level, bindings in `[keys]`, `[mouse]` and `[wheel]` tables mapping a
+/// chord, button or direction to an action:
+///
+/// ```toml
+/// fit_mode = "fit-width"
+///
+/// [keys]
+/// "ctrl+q" = "quit"
+/// j = "next"
+///
+/// [mouse]
+/// right = "pan"
+///
+/// [wheel]
+/// up = "prev"
+/// down = "next"
+/// ```
+#[derive(Default)]
+pub struct Config {
+    pub settings: Settings,
+    pub bindings: Bindings,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        Some(paths::config_dir()?.join("config.toml"))
+    }
+
+    /// Reads the config at `path`, falling back to the defaults when there is
+    /// no such file. Invalid entries are left at their defaults and described
+    /// in the returned errors, so one typo doesn't discard the whole file.
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return (Self::default(), vec![]),
+            Err(e) => return (Self::default(), vec![e.to_string()]),
+        };
+        let mut table: toml::Table = match toml::from_str(&text) {
+            Ok(table) => table,
+            Err(e) => return (Self::default(), vec![e.to_string()]),
+        };
+
+        let mut errors = Vec::new();
+        // entries are checked one by one so each bad action gets its own error
+        let mut bindings_table = |name: &str| -> HashMap<String, Action> {
+            let entries = match table.remove(name).map(toml::Value::try_into::<toml::Table>) {
+                Some(Ok(entries)) => entries,
+                Some(Err(e)) => {
+                    errors.push(format!("[{name}]: {e}"));
+                    return HashMap::new();
+                }
+                None => return HashMap::new(),
+            };
+            entries
+                .into_iter()
+                .filter_map(|(key, value)| match value.try_into() {
+                    Ok(action) => Some((key, action)),
+                    Err(e) => {
+                        errors.push(format!("{name}.\"{key}\": {}", e.to_string().trim()));
+                        None
+                    }
+                })
+                .collect()
+        };
+        let keys = bindings_table("keys");
+        let mouse = bindings_table("mouse");
+        let wheel = bindings_table("wheel");
+
+        // and so is each setting, so a bad one only loses itself
+        let invalid: Vec<_> = table
+            .iter()
+            .filter_map(|(key, value)| {
+                let entry = toml::Table::from_iter([(key.clone(), value.clone())]);
+                let e = entry.try_into::<Settings>().err()?;
+                errors.push(format!("{key}: {}", e.to_string().trim()));
+                Some(key.clone())
+            })
+            .collect();
+        for key in invalid {
+            table.remove(&key);
+        }
+        // an interval alone used to start the slideshow, before `slideshow`
+        if table.contains_key("slideshow_interval") && !table.contains_key("slideshow") {
+            table.insert("slideshow".to_owned(), true.into());
+        }
+
+        let settings = table.try_into().unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            Settings::default()
+        });
+        let mut bindings = Bindings::default();
+        errors.extend(bindings.extend(keys, mouse, wheel));
+
+        (Self { settings, bindings }, errors)
+    }
+}
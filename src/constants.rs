@@ -1,31 +1,64 @@
-use crate::vertex::Vertex;
 use ash::vk;
-use glam::{vec2, vec3};
-use std::ffi::c_char;
+use std::{ffi::c_char, time::Duration};
 
 pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
 pub const DEBUG_ENABLED_EXTENSION_NAMES: [*const c_char; 1] = [vk::EXT_DEBUG_UTILS_NAME.as_ptr()];
 pub const DEBUG_ENABLED_LAYER_NAMES: [*const c_char; 1] = [c"VK_LAYER_KHRONOS_validation".as_ptr()];
 pub const ENABLED_DEVICE_EXTENSION_NAMES: [*const c_char; 1] = [vk::KHR_SWAPCHAIN_NAME.as_ptr()];
-pub const VERTICES: [Vertex; 4] = [
-    Vertex {
-        pos: vec2(-0.5, -0.5),
-        color: vec3(1.0, 0.0, 0.0),
-    },
-    Vertex {
-        pos: vec2(0.5, -0.5),
-        color: vec3(0.0, 1.0, 0.0),
-    },
-    Vertex {
-        pos: vec2(0.5, 0.5),
-        color: vec3(0.0, 0.0, 1.0),
-    },
-    Vertex {
-        pos: vec2(-0.5, 0.5),
-        color: vec3(1.0, 1.0, 1.0),
-    },
-];
-pub const INDICES: [u32; 6] = [0, 1, 2, 2, 3, 0];
+pub const TEXTURE_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+pub const QUAD_INDICES: [u32; 6] = [0, 1, 2, 2, 3, 0];
+pub const MIN_ZOOM: f32 = 1.0 / 64.0;
+pub const MAX_ZOOM: f32 = 256.0;
+pub const ZOOM_STEP: f32 = 1.25;
+pub const PIXEL_GRID_MIN_ZOOM: f32 = 8.0;
+pub const MIN_CHECKER_SIZE: u32 = 2;
+pub const MAX_CHECKER_SIZE: u32 = 256;
+pub const MAX_WINDOW_FRACTION: f32 = 0.9;
+/// Linear color of the indicator shown while files are dragged over the window.
+pub const DROP_INDICATOR_COLOR: [f32; 3] = [0.05, 0.3, 0.9];
+pub const DROP_INDICATOR_OPACITY: f32 = 0.2;
+pub const DROP_INDICATOR_BORDER: f32 = 4.0;
+/// Quiet time after the last change to a watched file before reloading it.
+pub const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+pub const CURSOR_HIDE_DELAY: Duration = Duration::from_secs(2);
+/// How often to check on textures being uploaded, which signal no event.
+pub const UPLOAD_POLL_INTERVAL: Duration = Duration::from_millis(4);
+pub const PIXELS_PER_SCROLL_LINE: f32 = 40.0;
+/// Frame delays this short are stretched to `DEFAULT_FRAME_DELAY`, as
+/// browsers do.
+pub const MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+pub const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+pub const MIN_PLAYBACK_SPEED: f32 = 0.125;
+pub const MAX_PLAYBACK_SPEED: f32 = 8.0;
+pub const PLAYBACK_SPEED_STEP: f32 = 2.0;
+/// Images drawn in one frame, each placed by its own transform.
+pub const MAX_IMAGE_TRANSFORMS: usize = 4;
+pub const MAX_COMPARED_IMAGES: usize = MAX_IMAGE_TRANSFORMS;
+/// Linear color of the lines between compared images.
+pub const COMPARE_DIVIDER_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+pub const COMPARE_DIVIDER_WIDTH: f32 = 2.0;
+/// How far from the swipe divider, in window pixels, a drag moves it.
+pub const DIVIDER_GRAB_DISTANCE: f32 = 8.0;
+pub const PLAYLIST_PAGE_STEP: isize = 10;
+pub const MAX_DECODE_THREADS: usize = 4;
+pub const ALLOCATOR_BLOCK_SIZE: u64 = 64 * 1024 * 1024;
+pub const PREVIEW_BUDGET_FRACTION: f64 = 0.8;
+pub const MAX_TEXTURE_DESCRIPTOR_SETS: u32 = 1024;
+pub const MAX_MIPMAP_DESCRIPTOR_SETS: u32 = 1024;
+pub const TILE_OVERLAP: u32 = 16;
+/// Longest side of a thumbnail in texels.
+pub const THUMBNAIL_SIZE: u32 = 256;
+/// Side of the smallest thumbnail atlas, a whole number of thumbnails and no
+/// larger than every device supports. It grows with the window.
+pub const THUMBNAIL_ATLAS_SIZE: u32 = 4096;
+/// Thumbnails the instance buffers start out with room for.
+pub const INITIAL_GALLERY_INSTANCES: usize = 256;
+pub const GALLERY_CELL_SIZE: f32 = 192.0;
+pub const GALLERY_GAP: f32 = 16.0;
+/// Linear color of the outline around the selected thumbnail.
+pub const GALLERY_SELECTION_COLOR: [f32; 3] = [0.05, 0.3, 0.9];
+pub const GALLERY_SELECTION_BORDER: f32 = 4.0;
+pub const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
 
 pub fn check_physical_device_features(
     physical_device_features: vk::PhysicalDeviceFeatures,
@@ -0,0 +1,339 @@
+use crate::{
+    animation,
+    color::{self, AlphaMode},
+    orientation::Orientation,
+    thumbnail,
+    user_event::UserEvent,
+};
+use image::{ImageFormat, ImageReader, RgbaImage};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs::File,
+    io::{self, BufRead, BufReader, Cursor, Read, Seek},
+    iter,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use winit::event_loop::EventLoopProxy;
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<PathBuf>,
+    /// Changed files to decode again, ahead of everything pending.
+    reloads: VecDeque<PathBuf>,
+    in_progress: HashSet<PathBuf>,
+    /// Gallery thumbnails, after any full image.
+    thumbnails: VecDeque<PathBuf>,
+    thumbnails_in_progress: HashSet<PathBuf>,
+    shutdown: bool,
+}
+
+enum Job {
+    Decode(PathBuf),
+    Reload(PathBuf),
+    Thumbnail(PathBuf),
+}
+
+/// Premultiplied pixels as stored in the file, and how to turn them upright.
+pub struct DecodedImage {
+    /// The first frame of an animation.
+    pub pixels: RgbaImage,
+    pub orientation: Orientation,
+    /// Frames of an animation after the first.
+    pub later_frames: Vec<RgbaImage>,
+    /// How long each frame of an animation stays up, the first included.
+    /// Empty for still images.
+    pub delays: Vec<Duration>,
+    /// Times the file asks an animation to play, or 0 for forever.
+    pub loop_count: u32,
+}
+
+/// Pool of threads decoding images off the event loop thread. Results are
+/// delivered as `UserEvent::ImageDecoded`, `UserEvent::ImageChanged` for
+/// reloads and `UserEvent::ThumbnailDecoded` for thumbnails.
+pub struct Decoder {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Decoder {
+    /// Animations stop decoding frames once they take `max_bytes`.
+    pub fn new(
+        thread_count: usize,
+        source_alpha: AlphaMode,
+        max_bytes: u64,
+        proxy: EventLoopProxy<UserEvent>,
+    ) -> Self {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+
+        let workers = (0..thread_count)
+            .map(|i| {
+                let queue = Arc::clone(&queue);
+                let proxy = proxy.clone();
+                thread::Builder::new()
+                    .name(format!("decoder-{i}"))
+                    .spawn(move || worker(&queue, source_alpha, max_bytes, &proxy))
+                    .expect("Failed to spawn decoder thread.")
+            })
+            .collect();
+
+        Self { queue, workers }
+    }
+
+    /// Replaces everything still waiting in the queue with `paths`, most
+    /// urgent first. Paths already being decoded are not queued again.
+    pub fn request(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let (lock, cvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        queue.pending.clear();
+        for path in paths {
+            if !queue.in_progress.contains(&path) && !queue.pending.contains(&path) {
+                queue.pending.push_back(path);
+            }
+        }
+        cvar.notify_all();
+    }
+
+    /// Replaces the thumbnails still waiting in the queue with `paths`, most
+    /// urgent first.
+    pub fn request_thumbnails(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let (lock, cvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        queue.thumbnails.clear();
+        for path in paths {
+            if !queue.thumbnails_in_progress.contains(&path) && !queue.thumbnails.contains(&path) {
+                queue.thumbnails.push_back(path);
+            }
+        }
+        cvar.notify_all();
+    }
+
+    /// Decodes `path` again since the file changed on disk.
+    pub fn reload(&self, path: PathBuf) {
+        let (lock, cvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+
+        if !queue.reloads.contains(&path) {
+            queue.reloads.push_back(path);
+        }
+        cvar.notify_one();
+    }
+}
+
+/// Path standing for the image piped into standard input.
+pub const STDIN_PATH: &str = "-";
+
+impl DecodedImage {
+    fn new(
+        frames: Vec<(RgbaImage, Duration)>,
+        loop_count: u32,
+        orientation: Orientation,
+        source_alpha: AlphaMode,
+    ) -> Self {
+        let (mut frames, mut delays): (Vec<_>, Vec<_>) = frames.into_iter().unzip();
+        for frame in &mut frames {
+            color::premultiply(frame, source_alpha);
+        }
+        if frames.len() == 1 {
+            delays.clear();
+        }
+
+        let mut frames = frames.into_iter();
+        Self {
+            pixels: frames.next().unwrap(),
+            orientation,
+            later_frames: frames.collect(),
+            delays,
+            loop_count,
+        }
+    }
+
+    /// Every frame, just the one for still images.
+    pub fn frames(&self) -> impl Iterator<Item = &RgbaImage> {
+        iter::once(&self.pixels).chain(&self.later_frames)
+    }
+
+    /// Bytes of pixels over all frames.
+    pub fn size(&self) -> u64 {
+        self.frames().map(|x| x.as_raw().len() as u64).sum()
+    }
+}
+
+/// Decodes the image at `path`, with animations cut short once their frames
+/// take `max_bytes`.
+pub fn decode(
+    path: &Path,
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+) -> image::ImageResult<DecodedImage> {
+    if path.as_os_str() == STDIN_PATH {
+        return decode_bytes(stdin_bytes()?, source_alpha, max_bytes);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    let extension_format = ImageFormat::from_path(path).ok();
+    decode_reader(reader, extension_format, source_alpha, max_bytes)
+}
+
+/// Decodes the image at `path`, just the first frame of an animation, with its
+/// pixels as stored in the file.
+pub fn decode_raw(path: &Path) -> image::ImageResult<(RgbaImage, Orientation)> {
+    if path.as_os_str() == STDIN_PATH {
+        return decode_raw_reader(Cursor::new(stdin_bytes()?), None);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    decode_raw_reader(reader, ImageFormat::from_path(path).ok())
+}
+
+/// Decodes an image file held in memory, telling the format from its magic
+/// bytes.
+pub fn decode_bytes(
+    bytes: &[u8],
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+) -> image::ImageResult<DecodedImage> {
+    decode_reader(Cursor::new(bytes), None, source_alpha, max_bytes)
+}
+
+/// Decodes the image file in `reader`, telling the format from its magic
+/// bytes or else from `extension_format`.
+fn decode_reader(
+    mut reader: impl BufRead + Seek,
+    extension_format: Option<ImageFormat>,
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+) -> image::ImageResult<DecodedImage> {
+    let orientation = Orientation::read_from(&mut reader);
+    reader.rewind()?;
+
+    let format = ImageReader::new(&mut reader)
+        .with_guessed_format()?
+        .format();
+    if let Some(format) = format
+        && let Some(animation) = animation::decode_frames(&mut reader, format, max_bytes)?
+    {
+        return Ok(DecodedImage::new(
+            animation.frames,
+            animation.loop_count,
+            orientation,
+            source_alpha,
+        ));
+    }
+
+    reader.rewind()?;
+    let pixels = decode_still(reader, extension_format)?;
+    Ok(DecodedImage::new(
+        vec![(pixels, Duration::ZERO)],
+        0,
+        orientation,
+        source_alpha,
+    ))
+}
+
+fn decode_raw_reader(
+    mut reader: impl BufRead + Seek,
+    extension_format: Option<ImageFormat>,
+) -> image::ImageResult<(RgbaImage, Orientation)> {
+    let orientation = Orientation::read_from(&mut reader);
+    reader.rewind()?;
+    Ok((decode_still(reader, extension_format)?, orientation))
+}
+
+/// Decodes a still image, or the first frame of an animation.
+fn decode_still(
+    reader: impl BufRead + Seek,
+    extension_format: Option<ImageFormat>,
+) -> image::ImageResult<RgbaImage> {
+    let mut reader = ImageReader::new(reader);
+    if let Some(format) = extension_format {
+        reader.set_format(format);
+    }
+    Ok(reader.with_guessed_format()?.decode()?.into_rgba8())
+}
+
+/// Everything piped into standard input, read on first use and kept so the
+/// image can be decoded again after it leaves the cache.
+fn stdin_bytes() -> io::Result<&'static [u8]> {
+    static STDIN: OnceLock<io::Result<Vec<u8>>> = OnceLock::new();
+
+    let bytes = STDIN.get_or_init(|| {
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    });
+    match bytes {
+        Ok(bytes) => Ok(bytes),
+        Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+    }
+}
+
+fn worker(
+    queue: &(Mutex<Queue>, Condvar),
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+    proxy: &EventLoopProxy<UserEvent>,
+) {
+    let (lock, cvar) = queue;
+
+    loop {
+        let job = {
+            let mut queue = lock.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                if let Some(path) = queue.reloads.pop_front() {
+                    break Job::Reload(path);
+                }
+                if let Some(path) = queue.pending.pop_front() {
+                    queue.in_progress.insert(path.clone());
+                    break Job::Decode(path);
+                }
+                if let Some(path) = queue.thumbnails.pop_front() {
+                    queue.thumbnails_in_progress.insert(path.clone());
+                    break Job::Thumbnail(path);
+                }
+                queue = cvar.wait(queue).unwrap();
+            }
+        };
+
+        let event = match job {
+            Job::Decode(path) => {
+                let image = decode(&path, source_alpha, max_bytes);
+                lock.lock().unwrap().in_progress.remove(&path);
+                UserEvent::ImageDecoded { path, image }
+            }
+            Job::Reload(path) => {
+                let image = decode(&path, source_alpha, max_bytes);
+                UserEvent::ImageChanged { path, image }
+            }
+            Job::Thumbnail(path) => {
+                let image = thumbnail::generate(&path, source_alpha);
+                lock.lock().unwrap().thumbnails_in_progress.remove(&path);
+                UserEvent::ThumbnailDecoded { path, image }
+            }
+        };
+
+        if proxy.send_event(event).is_err() {
+            // event loop is gone
+            return;
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.queue;
+        lock.lock().unwrap().shutdown = true;
+        cvar.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use crate::{
+    allocator::{Allocator, AllocatorStats, MemoryBudget},
+    background::Background,
     buffer::Buffer,
     command_pool::CommandPool,
     constants::*,
@@ -7,25 +9,42 @@ use crate::{
     descriptor_set_layout::DescriptorSetLayout,
     device::Device,
     fence::Fence,
+    gpu_selector::GpuSelector,
     instance::Instance,
+    mesh::{Mesh, Quad},
+    mipmap::{self, MipChain, MipmapGenerator},
     physical_device::PhysicalDevice,
     pipeline::Pipeline,
+    present_mode::PresentMode,
+    push_constants::{OverlayPushConstants, PushConstants},
     queue::{QueueFamilyIndices, Queues},
     render_pass::RenderPass,
     semaphore::Semaphore,
+    settings::Settings,
     shader_module::ShaderModule,
     surface::Surface,
     swapchain::Swapchain,
+    texture::Texture,
+    texture_upload::{TextureUpload, ThumbnailUpload},
+    thumbnail_atlas::ThumbnailAtlas,
+    tiled_texture::{self, Tile, TiledTexture},
     uniform_buffer_object::UniformBufferObject,
-    vertex::Vertex,
+    vertex::{ThumbnailInstance, Vertex},
 };
+use ash::prelude::*;
 use ash::vk;
+use glam::{Vec2, uvec2};
 use std::{
     collections::HashSet,
+    error::Error,
     ffi::{CStr, c_char, c_void},
-    fs,
+    fs, mem,
+};
+use winit::{
+    event_loop::ActiveEventLoop,
+    raw_window_handle::HasDisplayHandle,
+    window::{Window, WindowAttributes},
 };
-use winit::{event_loop::ActiveEventLoop, raw_window_handle::HasDisplayHandle, window::Window};
 
 pub struct Engine {
     ash_entry: ash::Entry,
@@ -36,27 +55,68 @@ pub struct Engine {
     queue_family_indices: Option<QueueFamilyIndices>,
     physical_device: Option<PhysicalDevice>,
     device: Option<Device>,
+    allocator: Option<Allocator>,
+    memory_budget_supported: bool,
+    /// Whether textures get mip levels, either blitted or computed.
+    mipmaps_supported: bool,
+    /// Only created when the texture format can't be blitted with linear
+    /// filtering.
+    mipmap_generator: Option<MipmapGenerator>,
     queues: Option<Queues>,
     swapchain: Option<Swapchain>,
     render_pass: Option<RenderPass>,
     descriptor_set_layout: Option<DescriptorSetLayout>,
+    texture_set_layout: Option<DescriptorSetLayout>,
     graphics_pipeline: Option<Pipeline>,
+    /// Draws images added together, for the two halves of a transition.
+    additive_pipeline: Option<Pipeline>,
+    overlay_pipeline: Option<Pipeline>,
+    /// Fills in behind what was drawn, for the background of a transition.
+    underlay_pipeline: Option<Pipeline>,
+    gallery_pipeline: Option<Pipeline>,
+    /// Single quad instanced once per gallery thumbnail.
+    unit_quad: Option<Mesh>,
+    /// Per frame in flight, thumbnails drawn by the gallery.
+    instance_buffers: Option<Vec<Buffer>>,
     command_pool: Option<CommandPool>,
-    vertex_buffer: Option<Buffer>,
-    index_buffer: Option<Buffer>,
+    transfer_command_pool: Option<CommandPool>,
     uniform_buffers: Option<Vec<Buffer>>,
     descriptor_pool: Option<DescriptorPool>,
+    texture_descriptor_pool: Option<DescriptorPool>,
     descriptor_sets: Option<Vec<vk::DescriptorSet>>,
     command_buffers: Option<Vec<vk::CommandBuffer>>,
     image_available_sems: Option<Vec<Semaphore>>,
     render_finished_sems: Option<Vec<Semaphore>>,
     in_flight_fences: Option<Vec<Fence>>,
     current_frame: usize,
+    /// Resources to free once each frame in flight has finished with them.
+    retired: Vec<Vec<Retired>>,
+    gpu: Option<GpuSelector>,
+    present_mode: PresentMode,
+    /// Whether the window is created see-through, which only a transparent
+    /// background at startup asks for.
+    transparent: bool,
+}
+
+/// How a pipeline combines what it draws with what is already there.
+#[derive(Clone, Copy)]
+enum Blend {
+    Over,
+    Add,
+    /// Draws behind, showing through where the framebuffer is transparent.
+    Under,
+}
+
+/// Something a frame in flight may still use, freed once it finishes.
+enum Retired {
+    Texture(TiledTexture),
+    Buffer(Buffer),
+    Atlas(ThumbnailAtlas),
 }
 
 /// clean up on Drop
 impl Engine {
-    pub fn new(ash_entry: ash::Entry) -> Self {
+    pub fn new(ash_entry: ash::Entry, settings: &Settings) -> Self {
         Self {
             ash_entry,
             ash_instance: None,
@@ -66,40 +126,58 @@ impl Engine {
             queue_family_indices: None,
             physical_device: None,
             device: None,
+            allocator: None,
+            memory_budget_supported: false,
+            mipmaps_supported: false,
+            mipmap_generator: None,
             queues: None,
             swapchain: None,
             render_pass: None,
             descriptor_set_layout: None,
+            texture_set_layout: None,
             graphics_pipeline: None,
+            additive_pipeline: None,
+            overlay_pipeline: None,
+            underlay_pipeline: None,
+            gallery_pipeline: None,
+            unit_quad: None,
+            instance_buffers: None,
             command_pool: None,
-            vertex_buffer: None,
-            index_buffer: None,
+            transfer_command_pool: None,
             uniform_buffers: None,
             descriptor_pool: None,
+            texture_descriptor_pool: None,
             descriptor_sets: None,
             command_buffers: None,
             image_available_sems: None,
             render_finished_sems: None,
             in_flight_fences: None,
             current_frame: 0,
+            retired: (0..MAX_FRAMES_IN_FLIGHT).map(|_| Vec::new()).collect(),
+            gpu: settings.gpu.clone(),
+            present_mode: settings.present_mode,
+            transparent: settings.background == Background::Transparent,
         }
     }
 
-    pub fn init(&mut self, event_loop: &ActiveEventLoop) {
+    pub fn init(&mut self, event_loop: &ActiveEventLoop, window_attributes: WindowAttributes) {
         self.init_ash_instance(event_loop);
-        self.init_window(event_loop);
+        self.init_window(event_loop, window_attributes);
         self.init_surface();
         self.init_physical_device();
         self.init_logical_device();
+        self.init_allocator();
         self.init_swapchain();
         self.init_render_pass();
         self.init_descriptor_set_layout();
         self.init_graphics_pipeline();
+        self.init_overlay_pipeline();
+        self.init_gallery_pipeline();
+        self.init_mipmap_generator();
         self.init_framebuffers();
         self.init_command_pool();
-        self.init_vertex_buffer();
-        self.init_index_buffer();
         self.init_uniform_buffers();
+        self.init_gallery_buffers();
         self.init_descriptor_pool();
         self.init_descriptor_sets();
         self.init_command_buffers();
@@ -240,8 +318,10 @@ impl Engine {
         };
     }
 
-    fn init_window(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attributes = Window::default_attributes();
+    fn init_window(&mut self, event_loop: &ActiveEventLoop, window_attributes: WindowAttributes) {
+        // a transparent window costs the compositor a blend every frame, even
+        // where the swapchain alpha is 1, so it is left opaque unless asked for
+        let window_attributes = window_attributes.with_transparent(self.transparent);
         self.window = Some(
             event_loop
                 .create_window(window_attributes)
@@ -269,9 +349,16 @@ impl Engine {
 
         let mut chosen_device = None;
         let mut chosen_queue_family_indices = None;
-        for device in physical_devices {
+        for (index, device) in physical_devices.into_iter().enumerate() {
             let device = PhysicalDevice::from(device);
-            let queue_family_properties = device.query_queue_family_properties(&ash_instance);
+            if let Some(gpu) = &self.gpu {
+                let properties = device.query_properties(ash_instance);
+                let name = properties.device_name_as_c_str().unwrap_or_default();
+                if !gpu.matches(index, name) {
+                    continue;
+                }
+            }
+            let queue_family_properties = device.query_queue_family_properties(ash_instance);
 
             let surface = self.surface.as_ref().unwrap();
             let surface_instance = surface.instance();
@@ -296,6 +383,17 @@ impl Engine {
                 }
             }
 
+            // a transfer-only family is usually backed by a dedicated copy engine
+            queue_family_indices.transfer_family = queue_family_properties
+                .iter()
+                .position(|x| {
+                    x.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                        && !x
+                            .queue_flags
+                            .intersects(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+                })
+                .map(|i| i.try_into().unwrap());
+
             let supported_features = device.query_features(ash_instance);
 
             if !(device
@@ -323,7 +421,10 @@ impl Engine {
         }
 
         if chosen_device.is_none() || chosen_queue_family_indices.is_none() {
-            panic!("Failed to find suitable physical device");
+            match &self.gpu {
+                Some(gpu) => panic!("Failed to find suitable physical device matching {gpu:?}"),
+                None => panic!("Failed to find suitable physical device"),
+            }
         }
 
         self.physical_device = chosen_device;
@@ -336,8 +437,9 @@ impl Engine {
         let queue_family_indices = self.queue_family_indices.as_ref().unwrap();
         let present_family = queue_family_indices.present_family.unwrap();
         let graphics_family = queue_family_indices.graphics_family.unwrap();
+        let upload_family = queue_family_indices.upload_family().unwrap();
 
-        let unique_indices = HashSet::from([present_family, graphics_family]);
+        let unique_indices = HashSet::from([present_family, graphics_family, upload_family]);
 
         let mut queue_create_infos = Vec::with_capacity(unique_indices.len());
         let queue_priority = [1.0f32];
@@ -348,11 +450,19 @@ impl Engine {
             queue_create_infos.push(queue_create_info);
         }
 
+        let memory_budget_supported = physical_device
+            .support_extensions(ash_instance, &[vk::EXT_MEMORY_BUDGET_NAME.as_ptr()])
+            .unwrap();
+        let mut enabled_extension_names = Vec::from(ENABLED_DEVICE_EXTENSION_NAMES);
+        if memory_budget_supported {
+            enabled_extension_names.push(vk::EXT_MEMORY_BUDGET_NAME.as_ptr());
+        }
+
         let features = vk::PhysicalDeviceFeatures::default().sampler_anisotropy(true);
         let device_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(&queue_create_infos)
             .enabled_features(&features)
-            .enabled_extension_names(&ENABLED_DEVICE_EXTENSION_NAMES);
+            .enabled_extension_names(&enabled_extension_names);
 
         let device = physical_device
             .create_logical_device(ash_instance, &device_info, None)
@@ -362,12 +472,29 @@ impl Engine {
             Some(Queues {
                 graphics: device.device().get_device_queue(graphics_family, 0),
                 present: device.device().get_device_queue(present_family, 0),
+                transfer: device.device().get_device_queue(upload_family, 0),
             })
         };
         self.device = Some(device);
+        self.memory_budget_supported = memory_budget_supported;
+    }
+
+    fn init_allocator(&mut self) {
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let physical_device = self.physical_device.as_ref().unwrap();
+        let device_mem_props = physical_device.query_memory_properties(ash_instance);
+
+        self.allocator = Some(Allocator::new(device_mem_props));
     }
 
+    /// Creates the swapchain, or replaces the current one. Handing the old one
+    /// over lets the presentation engine keep showing its last image until
+    /// the new one has a frame, so resizing and fullscreen changes don't flash.
     fn init_swapchain(&mut self) {
+        let old_swapchain = self
+            .swapchain
+            .as_ref()
+            .map_or(vk::SwapchainKHR::null(), Swapchain::swapchain);
         let physical_device = self.physical_device.as_ref().unwrap();
         let surface = self.surface.as_ref().unwrap();
         let surface_instance = surface.instance();
@@ -391,7 +518,17 @@ impl Engine {
             physical_device
                 .query_supported_present_modes(surface_instance, surface)
                 .unwrap(),
-            vk::PresentModeKHR::FIFO, // prefer this for power saving
+            self.present_mode.to_vk(),
+        );
+
+        // the fragment shader outputs premultiplied alpha
+        let composite_alpha = Swapchain::choose_composite_alpha(
+            capabilities.supported_composite_alpha,
+            if self.transparent {
+                vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED
+            } else {
+                vk::CompositeAlphaFlagsKHR::OPAQUE
+            },
         );
 
         let max_image_count = capabilities.max_image_count;
@@ -426,17 +563,19 @@ impl Engine {
 
         swapchain_info = swapchain_info
             .pre_transform(capabilities.current_transform)
-            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .composite_alpha(composite_alpha)
             .present_mode(present_mode)
             .clipped(true)
-            .old_swapchain(vk::SwapchainKHR::null());
+            .old_swapchain(old_swapchain);
 
         let ash_instance = self.ash_instance.as_ref().unwrap().instance();
         let device = self.device.as_ref().unwrap();
         let swapchain = unsafe {
             Swapchain::new(ash_instance, device.device(), &swapchain_info, None).unwrap()
         };
-        self.swapchain = Some(swapchain);
+        if let Some(old_swapchain) = self.swapchain.replace(swapchain) {
+            unsafe { old_swapchain.cleanup(device.device(), None) };
+        }
     }
 
     fn init_render_pass(&mut self) {
@@ -497,14 +636,136 @@ impl Engine {
         let descriptor_set_layout =
             unsafe { DescriptorSetLayout::new(device, &layout_info, None).unwrap() };
 
+        // set 1, bound once per tile, with the filtered sampler at binding 0
+        // and the pixel art one at binding 1
+        let sampler_layout_binding = vk::DescriptorSetLayoutBinding::default()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+        let nearest_sampler_layout_binding = vk::DescriptorSetLayoutBinding::default()
+            .binding(1)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+        let bindings = [sampler_layout_binding, nearest_sampler_layout_binding];
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        let texture_set_layout =
+            unsafe { DescriptorSetLayout::new(device, &layout_info, None).unwrap() };
+
         self.descriptor_set_layout = Some(descriptor_set_layout);
+        self.texture_set_layout = Some(texture_set_layout);
     }
 
     fn init_graphics_pipeline(&mut self) {
+        let bind_desc = Vertex::get_binding_descriptions().unwrap();
+        let attr_desc = Vertex::get_attribute_descriptions().unwrap();
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&bind_desc)
+            .vertex_attribute_descriptions(&attr_desc);
+
+        let descriptor_set_layouts = [
+            self.descriptor_set_layout.as_ref().unwrap().layout(),
+            self.texture_set_layout.as_ref().unwrap().layout(),
+        ];
+        let push_constant_ranges = [vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(size_of::<PushConstants>().try_into().unwrap())];
+        let pipeline_layout_info = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(&descriptor_set_layouts)
+            .push_constant_ranges(&push_constant_ranges);
+
+        self.graphics_pipeline = Some(self.create_pipeline(
+            "build/shaders/vert.spv",
+            "build/shaders/frag.spv",
+            &vertex_input_state_info,
+            vk::PrimitiveTopology::TRIANGLE_LIST,
+            &pipeline_layout_info,
+            Blend::Over,
+        ));
+        self.additive_pipeline = Some(self.create_pipeline(
+            "build/shaders/vert.spv",
+            "build/shaders/frag.spv",
+            &vertex_input_state_info,
+            vk::PrimitiveTopology::TRIANGLE_LIST,
+            &pipeline_layout_info,
+            Blend::Add,
+        ));
+    }
+
+    /// Pipeline for flat rectangles drawn over the image, with the corners
+    /// generated in the vertex shader.
+    fn init_overlay_pipeline(&mut self) {
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default();
+
+        let push_constant_ranges = [vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(size_of::<OverlayPushConstants>().try_into().unwrap())];
+        let pipeline_layout_info =
+            vk::PipelineLayoutCreateInfo::default().push_constant_ranges(&push_constant_ranges);
+
+        self.overlay_pipeline = Some(self.create_pipeline(
+            "build/shaders/overlay_vert.spv",
+            "build/shaders/overlay_frag.spv",
+            &vertex_input_state_info,
+            vk::PrimitiveTopology::TRIANGLE_STRIP,
+            &pipeline_layout_info,
+            Blend::Over,
+        ));
+        self.underlay_pipeline = Some(self.create_pipeline(
+            "build/shaders/overlay_vert.spv",
+            "build/shaders/overlay_frag.spv",
+            &vertex_input_state_info,
+            vk::PrimitiveTopology::TRIANGLE_STRIP,
+            &pipeline_layout_info,
+            Blend::Under,
+        ));
+    }
+
+    /// Pipeline drawing gallery thumbnails from the atlas, one instance of
+    /// the unit quad each.
+    fn init_gallery_pipeline(&mut self) {
+        let bind_desc = Vertex::get_instanced_binding_descriptions().unwrap();
+        let attr_desc = Vertex::get_instanced_attribute_descriptions().unwrap();
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&bind_desc)
+            .vertex_attribute_descriptions(&attr_desc);
+
+        let descriptor_set_layouts = [
+            self.descriptor_set_layout.as_ref().unwrap().layout(),
+            self.texture_set_layout.as_ref().unwrap().layout(),
+        ];
+        let pipeline_layout_info =
+            vk::PipelineLayoutCreateInfo::default().set_layouts(&descriptor_set_layouts);
+
+        self.gallery_pipeline = Some(self.create_pipeline(
+            "build/shaders/gallery_vert.spv",
+            "build/shaders/gallery_frag.spv",
+            &vertex_input_state_info,
+            vk::PrimitiveTopology::TRIANGLE_LIST,
+            &pipeline_layout_info,
+            Blend::Over,
+        ));
+    }
+
+    /// Creates a pipeline drawing into the render pass with premultiplied
+    /// alpha blending and a dynamic viewport and scissor.
+    fn create_pipeline(
+        &self,
+        vert_shader_path: &str,
+        frag_shader_path: &str,
+        vertex_input_state_info: &vk::PipelineVertexInputStateCreateInfo,
+        topology: vk::PrimitiveTopology,
+        pipeline_layout_info: &vk::PipelineLayoutCreateInfo,
+        blend: Blend,
+    ) -> Pipeline {
         let device = self.device.as_ref().unwrap().device();
 
-        let vert_shader_code = fs::read("build/shaders/vert.spv").unwrap();
-        let frag_shader_code = fs::read("build/shaders/frag.spv").unwrap();
+        let vert_shader_code = fs::read(vert_shader_path).unwrap();
+        let frag_shader_code = fs::read(frag_shader_path).unwrap();
 
         let vert_shader_module = ShaderModule::new(device, &vert_shader_code, None).unwrap();
         let frag_shader_module = ShaderModule::new(device, &frag_shader_code, None).unwrap();
@@ -520,15 +781,9 @@ impl Engine {
             .name(c"main");
 
         let shader_stage_infos = [vert_shader_stage_info, frag_shader_stage_info];
-        let bind_desc = Vertex::get_binding_descriptions().unwrap();
-        let attr_desc = Vertex::get_attribute_descriptions().unwrap();
-
-        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
-            .vertex_binding_descriptions(&bind_desc)
-            .vertex_attribute_descriptions(&attr_desc);
 
         let input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo::default()
-            .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+            .topology(topology)
             .primitive_restart_enable(false);
 
         let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
@@ -543,7 +798,8 @@ impl Engine {
             .depth_clamp_enable(false)
             .rasterizer_discard_enable(false)
             .polygon_mode(vk::PolygonMode::FILL)
-            .cull_mode(vk::CullModeFlags::BACK)
+            // flipping the image reverses its winding
+            .cull_mode(vk::CullModeFlags::NONE)
             .front_face(vk::FrontFace::CLOCKWISE)
             .depth_bias_enable(false)
             .line_width(1.0f32);
@@ -552,13 +808,19 @@ impl Engine {
             .rasterization_samples(vk::SampleCountFlags::TYPE_1)
             .sample_shading_enable(false);
 
+        // textures hold premultiplied alpha
+        let (src_blend_factor, dst_blend_factor) = match blend {
+            Blend::Over => (vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA),
+            Blend::Add => (vk::BlendFactor::ONE, vk::BlendFactor::ONE),
+            Blend::Under => (vk::BlendFactor::ONE_MINUS_DST_ALPHA, vk::BlendFactor::ONE),
+        };
         let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState::default()
-            .blend_enable(false)
-            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .blend_enable(true)
+            .src_color_blend_factor(src_blend_factor)
+            .dst_color_blend_factor(dst_blend_factor)
             .color_blend_op(vk::BlendOp::ADD)
-            .src_alpha_blend_factor(vk::BlendFactor::ONE)
-            .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .src_alpha_blend_factor(src_blend_factor)
+            .dst_alpha_blend_factor(dst_blend_factor)
             .alpha_blend_op(vk::BlendOp::ADD)
             .color_write_mask(
                 vk::ColorComponentFlags::R
@@ -572,41 +834,64 @@ impl Engine {
             .logic_op_enable(false)
             .attachments(&color_blend_attachments);
 
-        let descriptor_set_layouts = [self.descriptor_set_layout.as_ref().unwrap().layout()];
-        let pipeline_layout_info =
-            vk::PipelineLayoutCreateInfo::default().set_layouts(&descriptor_set_layouts);
-
-        let graphics_pipeline_layout = unsafe {
+        let pipeline_layout = unsafe {
             device
-                .create_pipeline_layout(&pipeline_layout_info, None)
+                .create_pipeline_layout(pipeline_layout_info, None)
                 .unwrap()
         };
 
         let render_pass = self.render_pass.as_ref().unwrap();
-        let graphics_pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+        let pipeline_info = vk::GraphicsPipelineCreateInfo::default()
             .stages(&shader_stage_infos)
-            .vertex_input_state(&vertex_input_state_info)
+            .vertex_input_state(vertex_input_state_info)
             .input_assembly_state(&input_assembly_state_info)
             .viewport_state(&viewport_state_info)
             .rasterization_state(&rasterization_state_info)
             .multisample_state(&multisample_state_info)
             .color_blend_state(&color_blend_state_info)
             .dynamic_state(&dynamic_state_info)
-            .layout(graphics_pipeline_layout)
+            .layout(pipeline_layout)
             .render_pass(render_pass.render_pass())
             .subpass(0);
 
-        let create_infos = [graphics_pipeline_info];
-        let graphics_pipeline = unsafe {
+        let create_infos = [pipeline_info];
+        let pipelines = unsafe {
             device
                 .create_graphics_pipelines(vk::PipelineCache::null(), &create_infos, None)
                 .unwrap()
         };
 
-        self.graphics_pipeline = Some(Pipeline::from(
-            graphics_pipeline_layout,
-            graphics_pipeline[0],
-        ));
+        Pipeline::from(pipeline_layout, pipelines[0])
+    }
+
+    fn init_mipmap_generator(&mut self) {
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let device = self.device.as_ref().unwrap().device();
+        let physical_device = self.physical_device.as_ref().unwrap();
+
+        let format_props = physical_device.query_format_properties(ash_instance, TEXTURE_FORMAT);
+        let blit_features = vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+        if format_props.optimal_tiling_features.contains(blit_features) {
+            self.mipmaps_supported = true;
+            return;
+        }
+
+        // the fallback writes levels through storage views, without which
+        // textures make do with their first level
+        let storage_props =
+            physical_device.query_format_properties(ash_instance, vk::Format::R8G8B8A8_UNORM);
+        if !storage_props
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::STORAGE_IMAGE)
+        {
+            return;
+        }
+
+        self.mipmaps_supported = true;
+        let shader_code = fs::read("build/shaders/comp.spv").unwrap();
+        self.mipmap_generator = Some(MipmapGenerator::new(device, &shader_code, None).unwrap());
     }
 
     fn init_framebuffers(&mut self) {
@@ -632,71 +917,22 @@ impl Engine {
 
         let command_pool = unsafe { CommandPool::new(device, &command_pool_info, None).unwrap() };
 
-        self.command_pool = Some(command_pool);
-    }
-
-    fn init_vertex_buffer(&mut self) {
-        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
-        let device = self.device.as_ref().unwrap().device();
-        let physical_device = self.physical_device.as_ref().unwrap();
-        let device_mem_props = physical_device.query_memory_properties(ash_instance);
-
-        let buffer_size: vk::DeviceSize = size_of_val(&VERTICES).try_into().unwrap();
-        let buffer_info = vk::BufferCreateInfo::default()
-            .size(buffer_size)
-            .usage(vk::BufferUsageFlags::TRANSFER_SRC)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
-        let mut staging_buffer = Buffer::new(
-            device,
-            &buffer_info,
-            None,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            device_mem_props,
-        )
-        .unwrap();
-
-        unsafe {
-            staging_buffer
-                .map_memory(device, 0, vk::MemoryMapFlags::empty())
-                .unwrap();
-            staging_buffer
-                .ptr()
-                .unwrap()
-                .copy_from(VERTICES.as_ptr().cast(), buffer_size.try_into().unwrap());
-            staging_buffer.unmap_memory(device);
-        };
-
-        let buffer_info = vk::BufferCreateInfo::default()
-            .size(buffer_size)
-            .usage(vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
-        let vertex_buffer = Buffer::new(
-            device,
-            &buffer_info,
-            None,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            device_mem_props,
-        )
-        .unwrap();
-
-        self.copy_buffer_into(staging_buffer.buffer(), vertex_buffer.buffer(), buffer_size);
+        let transfer_command_pool_info = vk::CommandPoolCreateInfo::default()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(indices.upload_family().unwrap());
 
-        staging_buffer.cleanup(device, None);
+        let transfer_command_pool =
+            unsafe { CommandPool::new(device, &transfer_command_pool_info, None).unwrap() };
 
-        self.vertex_buffer = Some(vertex_buffer);
+        self.command_pool = Some(command_pool);
+        self.transfer_command_pool = Some(transfer_command_pool);
     }
 
-    fn copy_buffer_into(
-        &self,
-        src_buffer: vk::Buffer,
-        dst_buffer: vk::Buffer,
-        size: vk::DeviceSize,
-    ) {
-        let command_pool = self.command_pool.as_ref().unwrap();
+    fn begin_single_time_commands(&self, command_pool: vk::CommandPool) -> vk::CommandBuffer {
         let device = self.device.as_ref().unwrap().device();
 
         let command_buffer_info = vk::CommandBufferAllocateInfo::default()
-            .command_pool(command_pool.pool())
+            .command_pool(command_pool)
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(1);
 
@@ -714,81 +950,200 @@ impl Engine {
                 .unwrap()
         };
 
-        let copy_region = vk::BufferCopy::default().size(size);
-        let regions = [copy_region];
-        unsafe { device.cmd_copy_buffer(command_buffer, src_buffer, dst_buffer, &regions) };
+        command_buffer
+    }
 
-        unsafe { device.end_command_buffer(command_buffer).unwrap() };
+    /// Copies regions of `staging_buffer` into the first level of each tile's
+    /// layers on the upload queue, then fills in the remaining mip levels on the
+    /// graphics queue, without waiting.
+    fn submit_image_upload(
+        &self,
+        staging_buffer: Buffer,
+        texture: TiledTexture,
+        copy_regions: &[Vec<vk::BufferImageCopy>],
+        mip_chains: Vec<MipChain>,
+    ) -> TextureUpload {
+        let src_buffer = staging_buffer.buffer();
+        let copies: Vec<_> = texture
+            .tiles()
+            .iter()
+            .map(Tile::texture)
+            .zip(copy_regions)
+            .collect();
 
-        let command_buffers = [command_buffer];
-        let submit_info = vk::SubmitInfo::default().command_buffers(&command_buffers);
-        let submits = [submit_info];
-        let graphics_queue = self.queues.as_ref().unwrap().graphics;
+        let device = self.device.as_ref().unwrap().device();
+        let indices = self.queue_family_indices.as_ref().unwrap();
+        let queues = self.queues.as_ref().unwrap();
+        let graphics_pool = self.command_pool.as_ref().unwrap().pool();
+        let transfer_pool = self.transfer_command_pool.as_ref().unwrap().pool();
+        let dedicated_transfer = indices.has_dedicated_transfer();
+
+        let fence_info = vk::FenceCreateInfo::default();
+        let fence = unsafe { Fence::new(device, &fence_info, None).unwrap() };
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(vk::REMAINING_MIP_LEVELS)
+            .base_array_layer(0)
+            .layer_count(vk::REMAINING_ARRAY_LAYERS);
+
+        let to_transfer_dst: Vec<_> = copies
+            .iter()
+            .map(|(texture, _)| {
+                vk::ImageMemoryBarrier::default()
+                    .old_layout(vk::ImageLayout::UNDEFINED)
+                    .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(texture.image())
+                    .subresource_range(subresource_range)
+                    .src_access_mask(vk::AccessFlags::NONE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            })
+            .collect();
+
+        let transfer_command_buffer = self.begin_single_time_commands(transfer_pool);
         unsafe {
-            device
-                .queue_submit(graphics_queue, &submits, vk::Fence::null())
-                .unwrap();
-            device.queue_wait_idle(graphics_queue).unwrap();
-            device.free_command_buffers(command_pool.pool(), &command_buffers);
-        };
-    }
+            device.cmd_pipeline_barrier(
+                transfer_command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &to_transfer_dst,
+            );
+            for &(texture, regions) in &copies {
+                device.cmd_copy_buffer_to_image(
+                    transfer_command_buffer,
+                    src_buffer,
+                    texture.image(),
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    regions,
+                );
+            }
+        }
 
-    fn init_index_buffer(&mut self) {
-        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
-        let device = self.device.as_ref().unwrap().device();
-        let physical_device = self.physical_device.as_ref().unwrap();
-        let device_mem_props = physical_device.query_memory_properties(ash_instance);
+        // blits and dispatches need the graphics queue, so a dedicated
+        // transfer queue hands the images over first, keeping their layout
+        let mut command_buffers = vec![(transfer_pool, transfer_command_buffer)];
+        let mut semaphore = None;
+        let mip_command_buffer = if dedicated_transfer {
+            let release: Vec<_> = to_transfer_dst
+                .iter()
+                .map(|x| {
+                    x.old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .src_queue_family_index(indices.transfer_family.unwrap())
+                        .dst_queue_family_index(indices.graphics_family.unwrap())
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::NONE)
+                })
+                .collect();
+            let acquire: Vec<_> = release
+                .iter()
+                .map(|x| {
+                    x.src_access_mask(vk::AccessFlags::NONE).dst_access_mask(
+                        vk::AccessFlags::TRANSFER_READ
+                            | vk::AccessFlags::TRANSFER_WRITE
+                            | vk::AccessFlags::SHADER_READ,
+                    )
+                })
+                .collect();
+
+            let sem_info = vk::SemaphoreCreateInfo::default();
+            let transfer_done = unsafe { Semaphore::new(device, &sem_info, None).unwrap() };
 
-        let buffer_size: vk::DeviceSize = size_of_val(&INDICES).try_into().unwrap();
-        let buffer_info = vk::BufferCreateInfo::default()
-            .size(buffer_size)
-            .usage(vk::BufferUsageFlags::TRANSFER_SRC)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
-        let mut staging_buffer = Buffer::new(
-            device,
-            &buffer_info,
-            None,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            device_mem_props,
-        )
-        .unwrap();
+            unsafe {
+                device.cmd_pipeline_barrier(
+                    transfer_command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &release,
+                );
+                device.end_command_buffer(transfer_command_buffer).unwrap();
+
+                let command_buffers = [transfer_command_buffer];
+                let signal_sems = [transfer_done.sem()];
+                let submit_info = vk::SubmitInfo::default()
+                    .command_buffers(&command_buffers)
+                    .signal_semaphores(&signal_sems);
+                device
+                    .queue_submit(queues.transfer, &[submit_info], vk::Fence::null())
+                    .unwrap();
+            }
 
-        unsafe {
-            staging_buffer
-                .map_memory(device, 0, vk::MemoryMapFlags::empty())
-                .unwrap();
-            staging_buffer
-                .ptr()
-                .unwrap()
-                .copy_from(INDICES.as_ptr().cast(), buffer_size.try_into().unwrap());
-            staging_buffer.unmap_memory(device);
-        };
+            let graphics_command_buffer = self.begin_single_time_commands(graphics_pool);
+            unsafe {
+                device.cmd_pipeline_barrier(
+                    graphics_command_buffer,
+                    vk::PipelineStageFlags::TRANSFER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::TRANSFER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &acquire,
+                );
+            }
 
-        let buffer_info = vk::BufferCreateInfo::default()
-            .size(buffer_size)
-            .usage(vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
-        let index_buffer = Buffer::new(
-            device,
-            &buffer_info,
-            None,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
-            device_mem_props,
-        )
-        .unwrap();
+            command_buffers.push((graphics_pool, graphics_command_buffer));
+            semaphore = Some(transfer_done);
+            graphics_command_buffer
+        } else {
+            transfer_command_buffer
+        };
 
-        self.copy_buffer_into(staging_buffer.buffer(), index_buffer.buffer(), buffer_size);
+        match &self.mipmap_generator {
+            Some(generator) => {
+                for ((texture, _), chain) in copies.iter().zip(&mip_chains) {
+                    generator.record(device, mip_command_buffer, texture, chain);
+                }
+            }
+            // without mip levels this only makes the texture readable
+            None => {
+                for (texture, _) in &copies {
+                    mipmap::record_blits(device, mip_command_buffer, texture);
+                }
+            }
+        }
 
-        staging_buffer.cleanup(device, None);
+        unsafe {
+            device.end_command_buffer(mip_command_buffer).unwrap();
+
+            let command_buffers = [mip_command_buffer];
+            let wait_sems: Vec<_> = semaphore.iter().map(Semaphore::sem).collect();
+            let wait_stages =
+                [vk::PipelineStageFlags::TRANSFER | vk::PipelineStageFlags::COMPUTE_SHADER];
+            let submit_info = vk::SubmitInfo::default()
+                .wait_semaphores(&wait_sems)
+                .wait_dst_stage_mask(&wait_stages[..wait_sems.len()])
+                .command_buffers(&command_buffers);
+            let queue = if dedicated_transfer {
+                queues.graphics
+            } else {
+                queues.transfer
+            };
+            device
+                .queue_submit(queue, &[submit_info], fence.fence())
+                .unwrap();
+        }
 
-        self.index_buffer = Some(index_buffer);
+        TextureUpload::new(
+            texture,
+            staging_buffer,
+            command_buffers,
+            semaphore,
+            fence,
+            mip_chains,
+        )
     }
 
     fn init_uniform_buffers(&mut self) {
-        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
         let device = self.device.as_ref().unwrap().device();
-        let physical_device = self.physical_device.as_ref().unwrap();
-        let device_mem_props = physical_device.query_memory_properties(ash_instance);
+        let allocator = self.allocator.as_mut().unwrap();
 
         let buffer_size: vk::DeviceSize = size_of::<UniformBufferObject>().try_into().unwrap();
         let mut uniform_buffers = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
@@ -799,37 +1154,71 @@ impl Engine {
                 .usage(vk::BufferUsageFlags::UNIFORM_BUFFER)
                 .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-            let mut buffer = Buffer::new(
+            let buffer = Buffer::new(
                 device,
+                allocator,
                 &buffer_info,
                 None,
                 vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-                device_mem_props,
             )
             .unwrap();
 
-            buffer
-                .map_memory(device, 0, vk::MemoryMapFlags::empty())
-                .unwrap();
-
             uniform_buffers.push(buffer);
         }
 
         self.uniform_buffers = Some(uniform_buffers);
     }
 
+    fn init_gallery_buffers(&mut self) {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+
+        let quad = Quad {
+            pos_min: Vec2::splat(-0.5),
+            pos_max: Vec2::splat(0.5),
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ONE,
+        };
+        let unit_quad = Mesh::new(device, allocator, &[quad], None).unwrap();
+
+        let mut instance_buffers = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let buffer =
+                create_instance_buffer(device, allocator, INITIAL_GALLERY_INSTANCES).unwrap();
+            instance_buffers.push(buffer);
+        }
+
+        self.unit_quad = Some(unit_quad);
+        self.instance_buffers = Some(instance_buffers);
+    }
+
     fn init_descriptor_pool(&mut self) {
         let device = self.device.as_ref().unwrap().device();
 
-        let pool_size = vk::DescriptorPoolSize::default()
+        let ubo_pool_size = vk::DescriptorPoolSize::default()
+            .ty(vk::DescriptorType::UNIFORM_BUFFER)
             .descriptor_count(MAX_FRAMES_IN_FLIGHT.try_into().unwrap());
-        let pool_sizes = [pool_size];
+        let pool_sizes = [ubo_pool_size];
         let pool_info = vk::DescriptorPoolCreateInfo::default()
             .max_sets(MAX_FRAMES_IN_FLIGHT.try_into().unwrap())
             .pool_sizes(&pool_sizes);
 
         let pool = unsafe { DescriptorPool::new(device, &pool_info, None).unwrap() };
+
+        let sampler_pool_size = vk::DescriptorPoolSize::default()
+            .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(2 * MAX_TEXTURE_DESCRIPTOR_SETS);
+        let pool_sizes = [sampler_pool_size];
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+            .max_sets(MAX_TEXTURE_DESCRIPTOR_SETS)
+            .pool_sizes(&pool_sizes);
+
+        let texture_pool = unsafe { DescriptorPool::new(device, &pool_info, None).unwrap() };
+
         self.descriptor_pool = Some(pool);
+        self.texture_descriptor_pool = Some(texture_pool);
     }
 
     fn init_descriptor_sets(&mut self) {
@@ -838,7 +1227,7 @@ impl Engine {
         let device = self.device.as_ref().unwrap().device();
         let uniform_buffers = self.uniform_buffers.as_ref().unwrap();
 
-        let layouts = vec![layout; MAX_FRAMES_IN_FLIGHT.try_into().unwrap()];
+        let layouts = vec![layout; MAX_FRAMES_IN_FLIGHT];
         let alloc_info = vk::DescriptorSetAllocateInfo::default()
             .descriptor_pool(descriptor_pool)
             .set_layouts(&layouts);
@@ -898,17 +1287,715 @@ impl Engine {
         self.in_flight_fences = Some(in_flight_fences);
     }
 
-    pub fn recreate_swapchain(&mut self) {
-        let swapchain = self.swapchain.take().unwrap();
+    /// Rebuilds the swapchain for the window's current size, returning
+    /// whether it did.
+    pub fn recreate_swapchain(&mut self) -> bool {
+        // a minimized window has nothing to present to until it is restored
+        let size = self.window().inner_size();
+        if size.width == 0 || size.height == 0 {
+            return false;
+        }
+
+        let device = self.device.as_ref().unwrap().device();
+        unsafe { device.device_wait_idle().unwrap() };
+
+        self.init_swapchain();
+        self.init_framebuffers();
+        true
+    }
+
+    /// Starts uploading `images`, all the same size, into the layers of a new
+    /// sampled texture, split into tiles when they exceed the device's image
+    /// size limit. Fails when device or staging memory runs out.
+    pub fn begin_texture_upload(
+        &mut self,
+        images: &[&image::RgbaImage],
+    ) -> Result<TextureUpload, Box<dyn Error>> {
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let device = self.device.as_ref().unwrap().device();
+        let physical_device = self.physical_device.as_ref().unwrap();
+        let device_props = physical_device.query_properties(ash_instance);
+        let allocator = self.allocator.as_mut().unwrap();
+        let texture_set_layout = self.texture_set_layout.as_ref().unwrap().layout();
+        let descriptor_pool = self.texture_descriptor_pool.as_ref().unwrap().pool();
+
+        let extent = vk::Extent2D {
+            width: images[0].width(),
+            height: images[0].height(),
+        };
+        let regions = tiled_texture::layout(extent, device_props.limits.max_image_dimension2_d);
+        let layer_count: u32 = images.len().try_into().unwrap();
+        let layer_size = images[0].as_raw().len();
+
+        let buffer_size: vk::DeviceSize = (layer_size * images.len()).try_into().unwrap();
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(buffer_size)
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let staging_buffer = Buffer::new(
+            device,
+            allocator,
+            &buffer_info,
+            None,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        for (i, image) in images.iter().enumerate() {
+            let pixels = image.as_raw();
+            unsafe {
+                staging_buffer
+                    .ptr()
+                    .unwrap()
+                    .cast::<u8>()
+                    .add(i * layer_size)
+                    .copy_from(pixels.as_ptr(), pixels.len());
+            };
+        }
+
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .anisotropy_enable(true)
+            .max_anisotropy(device_props.limits.max_sampler_anisotropy)
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(vk::LOD_CLAMP_NONE);
+
+        // levels are either blitted or written by the compute fallback
+        // through a storage view of another format
+        let mipmaps_supported = self.mipmaps_supported;
+        let mip_levels = |extent| {
+            if mipmaps_supported {
+                mipmap::mip_levels(extent)
+            } else {
+                1
+            }
+        };
+        let (image_flags, mip_usage) = if self.mipmap_generator.is_some() {
+            (
+                vk::ImageCreateFlags::MUTABLE_FORMAT | vk::ImageCreateFlags::EXTENDED_USAGE,
+                vk::ImageUsageFlags::STORAGE,
+            )
+        } else {
+            (
+                vk::ImageCreateFlags::empty(),
+                vk::ImageUsageFlags::TRANSFER_SRC,
+            )
+        };
+
+        let quads: Vec<_> = regions.iter().map(|x| x.quad).collect();
+        let layouts = vec![texture_set_layout; regions.len()];
+        let alloc_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&layouts);
+
+        let mut textures = Vec::with_capacity(regions.len());
+        let mut create_tiles = || -> Result<_, Box<dyn Error>> {
+            for region in &regions {
+                let image_info = vk::ImageCreateInfo::default()
+                    .flags(image_flags)
+                    .image_type(vk::ImageType::TYPE_2D)
+                    .format(TEXTURE_FORMAT)
+                    .extent(region.rect.extent.into())
+                    .mip_levels(mip_levels(region.rect.extent))
+                    .array_layers(layer_count)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(
+                        vk::ImageUsageFlags::TRANSFER_DST
+                            | vk::ImageUsageFlags::SAMPLED
+                            | mip_usage,
+                    )
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED);
+                textures.push(Texture::new(
+                    device,
+                    allocator,
+                    &image_info,
+                    &sampler_info,
+                    None,
+                )?);
+            }
+
+            let mesh = Mesh::new(device, allocator, &quads, None)?;
+            match unsafe { device.allocate_descriptor_sets(&alloc_info) } {
+                Ok(sets) => Ok((mesh, sets)),
+                Err(e) => {
+                    mesh.cleanup(device, allocator, None);
+                    Err(e.into())
+                }
+            }
+        };
+        let (mesh, descriptor_sets) = match create_tiles() {
+            Ok(x) => x,
+            Err(e) => {
+                for texture in textures {
+                    texture.cleanup(device, allocator, None);
+                }
+                staging_buffer.cleanup(device, allocator, None);
+                return Err(e);
+            }
+        };
+
+        let mut copy_regions = Vec::with_capacity(regions.len());
+        for ((region, texture), set) in regions.iter().zip(&textures).zip(&descriptor_sets) {
+            write_texture_descriptor_set(device, *set, texture);
+
+            // the staging buffer holds the whole images one after another, so
+            // each tile reads a window of each with the image's row length
+            let offset = region.rect.offset;
+            let texel = offset.y as u64 * extent.width as u64 + offset.x as u64;
+            let layer_regions: Vec<_> = (0..layer_count)
+                .map(|layer| {
+                    vk::BufferImageCopy::default()
+                        .buffer_offset(layer as u64 * layer_size as u64 + texel * 4)
+                        .buffer_row_length(extent.width)
+                        .buffer_image_height(0)
+                        .image_subresource(
+                            vk::ImageSubresourceLayers::default()
+                                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                                .mip_level(0)
+                                .base_array_layer(layer)
+                                .layer_count(1),
+                        )
+                        .image_offset(vk::Offset3D::default())
+                        .image_extent(region.rect.extent.into())
+                })
+                .collect();
+            copy_regions.push(layer_regions);
+        }
+
+        let tiles = textures
+            .into_iter()
+            .zip(descriptor_sets)
+            .map(|(texture, set)| Tile::new(texture, set))
+            .collect();
+        let texture = TiledTexture::new(extent, tiles, mesh);
+
+        let mip_chains = match self.mipmap_chains(&texture) {
+            Ok(x) => x,
+            Err(e) => {
+                let device = self.device.as_ref().unwrap().device();
+                let allocator = self.allocator.as_mut().unwrap();
+                texture.cleanup(device, allocator, descriptor_pool, None);
+                staging_buffer.cleanup(device, allocator, None);
+                return Err(e.into());
+            }
+        };
+
+        Ok(self.submit_image_upload(staging_buffer, texture, &copy_regions, mip_chains))
+    }
+
+    /// Descriptor sets for the compute fallback to fill each tile's mip
+    /// levels, if it is used.
+    fn mipmap_chains(&self, texture: &TiledTexture) -> VkResult<Vec<MipChain>> {
+        let Some(generator) = &self.mipmap_generator else {
+            return Ok(Vec::new());
+        };
+        let device = self.device.as_ref().unwrap().device();
+
+        let mut chains = Vec::new();
+        for tile in texture.tiles() {
+            match generator.allocate(device, tile.texture()) {
+                Ok(chain) => chains.push(chain),
+                Err(e) => {
+                    for chain in chains {
+                        chain.cleanup(device, generator.descriptor_pool(), None);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(chains)
+    }
+
+    pub fn is_upload_complete(&self, upload: &TextureUpload) -> bool {
         let device = self.device.as_ref().unwrap().device();
+        upload.is_complete(device).unwrap()
+    }
+
+    /// Blocks until `upload` is complete and returns its texture.
+    pub fn finish_texture_upload(&mut self, upload: TextureUpload) -> TiledTexture {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+        let mipmap_generator = self.mipmap_generator.as_ref();
+        upload.wait(device).unwrap();
+        upload.finish(device, allocator, mipmap_generator, None)
+    }
+
+    /// Frees `texture` once the frames in flight that may sample from it have
+    /// finished, in `free_retired`.
+    pub fn destroy_texture(&mut self, texture: TiledTexture) {
+        self.retire(Retired::Texture(texture));
+    }
+
+    /// Holds on to `resource` until the frames already submitted finish.
+    fn retire(&mut self, resource: Retired) {
+        // the frame submitted last is the last one that may use it
+        let frame = (self.current_frame + MAX_FRAMES_IN_FLIGHT - 1) % MAX_FRAMES_IN_FLIGHT;
+        self.retired[frame].push(resource);
+    }
+
+    /// Frees what was retired before each frame whose fence has signaled,
+    /// without waiting on the rest.
+    pub fn free_retired(&mut self) {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+        let descriptor_pool = self.texture_descriptor_pool.as_ref().unwrap().pool();
+        let fences = self.in_flight_fences.as_ref().unwrap();
+
+        for (fence, retired) in fences.iter().zip(&mut self.retired) {
+            if retired.is_empty() || !unsafe { device.get_fence_status(fence.fence()) }.unwrap() {
+                continue;
+            }
+            for resource in retired.drain(..) {
+                resource.cleanup(device, allocator, descriptor_pool);
+            }
+        }
+    }
+
+    pub fn has_retired(&self) -> bool {
+        self.retired.iter().any(|x| !x.is_empty())
+    }
+
+    /// Waits for the frames still using retired resources and frees them, for
+    /// when an allocation needs their memory back right away.
+    pub fn wait_retired(&mut self) {
+        let device = self.device.as_ref().unwrap().device();
+        let in_flight_fences = self.in_flight_fences.as_ref().unwrap();
+        let fences: Vec<_> = in_flight_fences
+            .iter()
+            .zip(&self.retired)
+            .filter(|(_, retired)| !retired.is_empty())
+            .map(|(fence, _)| fence.fence())
+            .collect();
+        if !fences.is_empty() {
+            unsafe { device.wait_for_fences(&fences, true, u64::MAX).unwrap() };
+        }
+        self.free_retired();
+    }
+
+    /// Side of the smallest thumbnail atlas with room for `slot_count`
+    /// thumbnails, or of the largest one the device supports.
+    pub fn thumbnail_atlas_size(&self, slot_count: usize) -> u32 {
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let physical_device = self.physical_device.as_ref().unwrap();
+        let max_size = physical_device
+            .query_properties(ash_instance)
+            .limits
+            .max_image_dimension2_d;
+        let columns = (slot_count as f64).sqrt().ceil() as u32;
+        (columns.next_power_of_two() * THUMBNAIL_SIZE).clamp(
+            THUMBNAIL_ATLAS_SIZE,
+            max_size / THUMBNAIL_SIZE * THUMBNAIL_SIZE,
+        )
+    }
+
+    /// Creates an empty thumbnail atlas `size` texels on a side, as given by
+    /// `thumbnail_atlas_size`. It is cleared by `record_atlas_clear` before it
+    /// is first drawn from.
+    pub fn create_thumbnail_atlas(&mut self, size: u32) -> Result<ThumbnailAtlas, Box<dyn Error>> {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+        let texture_set_layout = self.texture_set_layout.as_ref().unwrap().layout();
+        let descriptor_pool = self.texture_descriptor_pool.as_ref().unwrap().pool();
+
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(TEXTURE_FORMAT)
+            .extent(vk::Extent3D {
+                width: size,
+                height: size,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .min_lod(0.0)
+            .max_lod(0.0);
+        let texture = Texture::new(device, allocator, &image_info, &sampler_info, None)?;
+
+        let layouts = [texture_set_layout];
+        let alloc_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&layouts);
+        let descriptor_set = match unsafe { device.allocate_descriptor_sets(&alloc_info) } {
+            Ok(sets) => sets[0],
+            Err(e) => {
+                texture.cleanup(device, allocator, None);
+                return Err(e.into());
+            }
+        };
+        write_texture_descriptor_set(device, descriptor_set, &texture);
+
+        Ok(ThumbnailAtlas::new(texture, descriptor_set))
+    }
+
+    /// Records clearing `atlas` to transparent, ahead of the render pass of
+    /// the first frame drawing from it.
+    pub fn record_atlas_clear(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        atlas: &mut ThumbnailAtlas,
+    ) {
+        let device = self.device.as_ref().unwrap().device();
+
+        let to_transfer_dst = vk::ImageMemoryBarrier::default()
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(atlas.texture().image())
+            .subresource_range(color_subresource_range())
+            .src_access_mask(vk::AccessFlags::NONE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+        let to_shader_read = to_transfer_dst
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ);
 
         unsafe {
-            device.device_wait_idle().unwrap();
-            swapchain.cleanup(device, None);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_transfer_dst],
+            );
+            device.cmd_clear_color_image(
+                command_buffer,
+                atlas.texture().image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue::default(),
+                &[color_subresource_range()],
+            );
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_shader_read],
+            );
+        }
+
+        atlas.set_cleared();
+    }
+
+    /// Copies thumbnails back to back into device local memory on the upload
+    /// queue, without waiting. Once the upload is complete,
+    /// `record_thumbnail_copies` moves them into the atlas.
+    pub fn begin_thumbnail_upload(
+        &mut self,
+        thumbnails: &[&image::RgbaImage],
+    ) -> Result<ThumbnailUpload, Box<dyn Error>> {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+
+        let buffer_size: usize = thumbnails.iter().map(|x| x.as_raw().len()).sum();
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(buffer_size.try_into()?)
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let staging_buffer = Buffer::new(
+            device,
+            allocator,
+            &buffer_info,
+            None,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        let mut offset = 0;
+        for image in thumbnails {
+            let pixels = image.as_raw();
+            unsafe {
+                staging_buffer
+                    .ptr()
+                    .unwrap()
+                    .cast::<u8>()
+                    .add(offset)
+                    .copy_from(pixels.as_ptr(), pixels.len())
+            };
+            offset += pixels.len();
+        }
+
+        let buffer_info = buffer_info
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST);
+        let buffer = match Buffer::new(
+            device,
+            allocator,
+            &buffer_info,
+            None,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                staging_buffer.cleanup(device, allocator, None);
+                return Err(e);
+            }
         };
 
-        self.init_swapchain();
-        self.init_framebuffers();
+        let indices = self.queue_family_indices.as_ref().unwrap();
+        let queues = self.queues.as_ref().unwrap();
+        let transfer_pool = self.transfer_command_pool.as_ref().unwrap().pool();
+        let fence_info = vk::FenceCreateInfo::default();
+        let fence = unsafe { Fence::new(device, &fence_info, None).unwrap() };
+
+        let command_buffer = self.begin_single_time_commands(transfer_pool);
+        unsafe {
+            device.cmd_copy_buffer(
+                command_buffer,
+                staging_buffer.buffer(),
+                buffer.buffer(),
+                &[vk::BufferCopy::default().size(buffer_info.size)],
+            );
+            // the atlas is copied into on the graphics queue, so a dedicated
+            // transfer queue hands the buffer over
+            if indices.has_dedicated_transfer() {
+                device.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[thumbnail_buffer_barrier(indices, buffer.buffer())
+                        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::NONE)],
+                    &[],
+                );
+            }
+            device.end_command_buffer(command_buffer).unwrap();
+
+            let command_buffers = [command_buffer];
+            let submit_info = vk::SubmitInfo::default().command_buffers(&command_buffers);
+            device
+                .queue_submit(queues.transfer, &[submit_info], fence.fence())
+                .unwrap();
+        }
+
+        let sizes = thumbnails
+            .iter()
+            .map(|x| uvec2(x.width(), x.height()))
+            .collect();
+        Ok(ThumbnailUpload::new(
+            buffer,
+            staging_buffer,
+            (transfer_pool, command_buffer),
+            fence,
+            sizes,
+        ))
+    }
+
+    pub fn is_thumbnail_upload_complete(&self, upload: &ThumbnailUpload) -> bool {
+        let device = self.device.as_ref().unwrap().device();
+        upload.is_complete(device).unwrap()
+    }
+
+    /// Records copies of a complete upload's thumbnails into the atlas cells
+    /// at `offsets`, skipping those without one, before `command_buffer`
+    /// samples the atlas. The thumbnails are freed once the frame finishes.
+    pub fn record_thumbnail_copies(
+        &mut self,
+        command_buffer: vk::CommandBuffer,
+        atlas: &ThumbnailAtlas,
+        upload: ThumbnailUpload,
+        offsets: &[Option<vk::Offset2D>],
+    ) {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+        let indices = self.queue_family_indices.as_ref().unwrap();
+
+        let mut offset = 0;
+        let mut copy_regions = Vec::with_capacity(offsets.len());
+        for (image_offset, size) in offsets.iter().zip(upload.sizes()) {
+            if let Some(image_offset) = image_offset {
+                copy_regions.push(
+                    vk::BufferImageCopy::default()
+                        .buffer_offset(offset)
+                        .image_subresource(
+                            vk::ImageSubresourceLayers::default()
+                                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                                .mip_level(0)
+                                .base_array_layer(0)
+                                .layer_count(1),
+                        )
+                        .image_offset(vk::Offset3D {
+                            x: image_offset.x,
+                            y: image_offset.y,
+                            z: 0,
+                        })
+                        .image_extent(vk::Extent3D {
+                            width: size.x,
+                            height: size.y,
+                            depth: 1,
+                        }),
+                );
+            }
+            offset += size.x as vk::DeviceSize * size.y as vk::DeviceSize * 4;
+        }
+        let buffer = upload.finish(device, allocator, None);
+
+        let to_transfer_dst = vk::ImageMemoryBarrier::default()
+            .old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(atlas.texture().image())
+            .subresource_range(color_subresource_range())
+            .src_access_mask(vk::AccessFlags::SHADER_READ)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+        let to_shader_read = to_transfer_dst
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ);
+        // takes the buffer over from a dedicated transfer queue
+        let acquire: Vec<_> = indices
+            .has_dedicated_transfer()
+            .then(|| {
+                thumbnail_buffer_barrier(indices, buffer.buffer())
+                    .src_access_mask(vk::AccessFlags::NONE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            })
+            .into_iter()
+            .collect();
+
+        unsafe {
+            // earlier frames on the same queue finish sampling first
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &acquire,
+                &[to_transfer_dst],
+            );
+            if !copy_regions.is_empty() {
+                device.cmd_copy_buffer_to_image(
+                    command_buffer,
+                    buffer.buffer(),
+                    atlas.texture().image(),
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &copy_regions,
+                );
+            }
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_shader_read],
+            );
+        }
+
+        // read by the frame being recorded, whose fence is only waited on
+        // again after it is submitted
+        self.retired[self.current_frame].push(Retired::Buffer(buffer));
+    }
+
+    /// Waits for `upload` and frees it without copying anything.
+    pub fn destroy_thumbnail_upload(&mut self, upload: ThumbnailUpload) {
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+        upload.wait(device).unwrap();
+        upload
+            .finish(device, allocator, None)
+            .cleanup(device, allocator, None);
+    }
+
+    /// Frees `atlas` once the frames in flight that may sample from it have
+    /// finished, in `free_retired`.
+    pub fn destroy_thumbnail_atlas(&mut self, atlas: ThumbnailAtlas) {
+        self.retire(Retired::Atlas(atlas));
+    }
+
+    /// Most images one texture can hold as layers.
+    pub fn max_texture_layers(&self) -> u32 {
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let physical_device = self.physical_device.as_ref().unwrap();
+        physical_device
+            .query_properties(ash_instance)
+            .limits
+            .max_image_array_layers
+    }
+
+    pub fn memory_stats(&self) -> AllocatorStats {
+        self.allocator.as_ref().unwrap().stats()
+    }
+
+    /// Budget and usage summed over the device local heaps, when the driver
+    /// reports them through `VK_EXT_memory_budget`.
+    pub fn memory_budget(&self) -> Option<MemoryBudget> {
+        if !self.memory_budget_supported {
+            return None;
+        }
+
+        let ash_instance = self.ash_instance.as_ref().unwrap().instance();
+        let physical_device = self.physical_device.as_ref().unwrap();
+        let (device_mem_props, heap_budgets) = physical_device.query_memory_budget(ash_instance);
+
+        let heap_count = device_mem_props.memory_heap_count as usize;
+        let budget = device_mem_props.memory_heaps[..heap_count]
+            .iter()
+            .enumerate()
+            .filter(|(_, heap)| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .fold(MemoryBudget::default(), |acc, (i, _)| MemoryBudget {
+                budget: acc.budget + heap_budgets.heap_budget[i],
+                usage: acc.usage + heap_budgets.heap_usage[i],
+            });
+
+        Some(budget)
+    }
+
+    /// Device local memory textures can still take: what the driver budget
+    /// leaves plus what is free in blocks the allocator already holds, which
+    /// the budget counts as used.
+    pub fn available_memory(&self) -> Option<vk::DeviceSize> {
+        let free = self
+            .allocator
+            .as_ref()
+            .unwrap()
+            .free_bytes(vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        Some(self.memory_budget()?.available() + free)
+    }
+
+    /// Whether the compositor blends the window with what is behind it. The
+    /// window can't be made transparent after it is created, so this stays
+    /// false unless the background started out transparent.
+    pub fn supports_transparency(&self) -> bool {
+        self.transparent
+            && self.swapchain.as_ref().unwrap().composite_alpha()
+                == vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED
     }
 }
 
@@ -942,6 +2029,41 @@ impl Engine {
     pub fn descriptor_set(&self) -> vk::DescriptorSet {
         self.descriptor_sets.as_ref().unwrap()[self.current_frame]
     }
+
+    pub fn instance_buffer(&self) -> vk::Buffer {
+        self.instance_buffers.as_ref().unwrap()[self.current_frame].buffer()
+    }
+
+    /// Room for `instance_capacity` thumbnails.
+    pub fn instance_buffer_ptr(&self) -> *mut ThumbnailInstance {
+        self.instance_buffers.as_ref().unwrap()[self.current_frame]
+            .ptr()
+            .unwrap()
+            .cast()
+    }
+
+    /// Thumbnails this frame's instance buffer has room for.
+    pub fn instance_capacity(&self) -> usize {
+        let buffer = &self.instance_buffers.as_ref().unwrap()[self.current_frame];
+        usize::try_from(buffer.size()).unwrap() / size_of::<ThumbnailInstance>()
+    }
+
+    /// Grows this frame's instance buffer to hold at least `count`
+    /// thumbnails. Only call this while recording the frame, after its fence
+    /// was waited on, so the old buffer is no longer in use.
+    pub fn reserve_instances(&mut self, count: usize) -> Result<(), Box<dyn Error>> {
+        if count <= self.instance_capacity() {
+            return Ok(());
+        }
+        let device = self.device.as_ref().unwrap().device();
+        let allocator = self.allocator.as_mut().unwrap();
+
+        let buffer = create_instance_buffer(device, allocator, count.next_power_of_two())?;
+        let instance_buffers = self.instance_buffers.as_mut().unwrap();
+        mem::replace(&mut instance_buffers[self.current_frame], buffer)
+            .cleanup(device, allocator, None);
+        Ok(())
+    }
 }
 
 impl Engine {
@@ -973,12 +2095,24 @@ impl Engine {
         self.graphics_pipeline.as_ref().unwrap()
     }
 
-    pub fn vertex_buffer(&self) -> &Buffer {
-        self.vertex_buffer.as_ref().unwrap()
+    pub fn additive_pipeline(&self) -> &Pipeline {
+        self.additive_pipeline.as_ref().unwrap()
+    }
+
+    pub fn overlay_pipeline(&self) -> &Pipeline {
+        self.overlay_pipeline.as_ref().unwrap()
     }
 
-    pub fn index_buffer(&self) -> &Buffer {
-        self.index_buffer.as_ref().unwrap()
+    pub fn underlay_pipeline(&self) -> &Pipeline {
+        self.underlay_pipeline.as_ref().unwrap()
+    }
+
+    pub fn gallery_pipeline(&self) -> &Pipeline {
+        self.gallery_pipeline.as_ref().unwrap()
+    }
+
+    pub fn unit_quad(&self) -> &Mesh {
+        self.unit_quad.as_ref().unwrap()
     }
 
     pub fn framebuffer(&self, image_index: usize) -> vk::Framebuffer {
@@ -991,6 +2125,25 @@ impl Engine {
     }
 }
 
+impl Retired {
+    fn cleanup(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        texture_descriptor_pool: vk::DescriptorPool,
+    ) {
+        match self {
+            Retired::Texture(texture) => {
+                texture.cleanup(device, allocator, texture_descriptor_pool, None)
+            }
+            Retired::Buffer(buffer) => buffer.cleanup(device, allocator, None),
+            Retired::Atlas(atlas) => {
+                atlas.cleanup(device, allocator, texture_descriptor_pool, None)
+            }
+        }
+    }
+}
+
 impl Drop for Engine {
     fn drop(&mut self) {
         let device = self.device.as_ref().unwrap().device();
@@ -998,33 +2151,63 @@ impl Drop for Engine {
         unsafe {
             device.device_wait_idle().unwrap();
 
+            let allocator = self.allocator.as_mut().unwrap();
+            let texture_descriptor_pool = self.texture_descriptor_pool.as_ref().unwrap().pool();
+            for resource in self.retired.drain(..).flatten() {
+                resource.cleanup(device, allocator, texture_descriptor_pool);
+            }
+
             self.image_available_sems
                 .take()
                 .unwrap()
                 .into_iter()
-                .chain(self.render_finished_sems.take().unwrap().into_iter())
+                .chain(self.render_finished_sems.take().unwrap())
                 .for_each(|x| x.cleanup(device, None));
             self.in_flight_fences
                 .take()
                 .unwrap()
                 .into_iter()
                 .for_each(|x| x.cleanup(device, None));
+            self.texture_descriptor_pool
+                .take()
+                .unwrap()
+                .cleanup(device, None);
             self.descriptor_pool.take().unwrap().cleanup(device, None);
+            let allocator = self.allocator.as_mut().unwrap();
             self.uniform_buffers
                 .take()
                 .unwrap()
                 .into_iter()
-                .for_each(|x| x.cleanup(device, None));
-            self.index_buffer.take().unwrap().cleanup(device, None);
-            self.vertex_buffer.take().unwrap().cleanup(device, None);
+                .chain(self.instance_buffers.take().unwrap())
+                .for_each(|x| x.cleanup(device, allocator, None));
+            self.unit_quad
+                .take()
+                .unwrap()
+                .cleanup(device, allocator, None);
+            self.transfer_command_pool
+                .take()
+                .unwrap()
+                .cleanup(device, None);
             self.command_pool.take().unwrap().cleanup(device, None);
+            self.texture_set_layout
+                .take()
+                .unwrap()
+                .cleanup(device, None);
             self.descriptor_set_layout
                 .take()
                 .unwrap()
                 .cleanup(device, None);
             self.render_pass.take().unwrap().cleanup(device, None);
             self.graphics_pipeline.take().unwrap().cleanup(device, None);
+            self.additive_pipeline.take().unwrap().cleanup(device, None);
+            self.overlay_pipeline.take().unwrap().cleanup(device, None);
+            self.underlay_pipeline.take().unwrap().cleanup(device, None);
+            self.gallery_pipeline.take().unwrap().cleanup(device, None);
+            if let Some(mipmap_generator) = self.mipmap_generator.take() {
+                mipmap_generator.cleanup(device, None);
+            }
             self.swapchain.take().unwrap().cleanup(device, None);
+            self.allocator.take().unwrap().cleanup(device, None);
             self.surface.take().unwrap().cleanup(None);
             if cfg!(debug_assertions) {
                 self.debug_messenger.take().unwrap().cleanup(None);
@@ -1034,3 +2217,73 @@ impl Drop for Engine {
         }
     }
 }
+
+/// Points both bindings of a texture set at `texture`, filtered and nearest.
+fn write_texture_descriptor_set(device: &ash::Device, set: vk::DescriptorSet, texture: &Texture) {
+    let image_info = vk::DescriptorImageInfo::default()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(texture.view())
+        .sampler(texture.sampler());
+    let nearest_image_info = image_info.sampler(texture.nearest_sampler());
+    let image_infos = [image_info];
+    let nearest_image_infos = [nearest_image_info];
+    let desc_writes = [
+        vk::WriteDescriptorSet::default()
+            .dst_set(set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_count(1)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image_infos),
+        vk::WriteDescriptorSet::default()
+            .dst_set(set)
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_count(1)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&nearest_image_infos),
+    ];
+    unsafe { device.update_descriptor_sets(&desc_writes, &[]) };
+}
+
+fn color_subresource_range() -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1)
+}
+
+/// Ownership transfer of a thumbnail upload buffer from the transfer queue to
+/// the graphics queue, recorded as a release on one and an acquire on the other.
+/// Host visible vertex buffer with room for `capacity` thumbnails.
+fn create_instance_buffer(
+    device: &ash::Device,
+    allocator: &mut Allocator,
+    capacity: usize,
+) -> Result<Buffer, Box<dyn Error>> {
+    let buffer_info = vk::BufferCreateInfo::default()
+        .size((capacity * size_of::<ThumbnailInstance>()).try_into()?)
+        .usage(vk::BufferUsageFlags::VERTEX_BUFFER)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE);
+    Buffer::new(
+        device,
+        allocator,
+        &buffer_info,
+        None,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )
+}
+
+fn thumbnail_buffer_barrier(
+    indices: &QueueFamilyIndices,
+    buffer: vk::Buffer,
+) -> vk::BufferMemoryBarrier<'static> {
+    vk::BufferMemoryBarrier::default()
+        .src_queue_family_index(indices.transfer_family.unwrap())
+        .dst_queue_family_index(indices.graphics_family.unwrap())
+        .buffer(buffer)
+        .offset(0)
+        .size(vk::WHOLE_SIZE)
+}
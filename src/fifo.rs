@@ -0,0 +1,85 @@
+use crate::{
+    color::AlphaMode,
+    decoder::{self, DecodedImage},
+    user_event::UserEvent,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+};
+use winit::event_loop::EventLoopProxy;
+
+/// Waits for a writer to open the named pipe at `path` and decodes what it
+/// writes before closing it. Each writer delivers one image.
+pub fn read_image(
+    path: &Path,
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+) -> image::ImageResult<DecodedImage> {
+    decoder::decode_bytes(&read_bytes(path)?, source_alpha, max_bytes)
+}
+
+/// Waits for a writer and returns everything it writes.
+fn read_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    // anything else reads the same bytes right away, over and over
+    if !is_fifo(path)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a named pipe",
+        ));
+    }
+    loop {
+        let bytes = fs::read(path)?;
+        // a writer that closes without writing anything isn't an image
+        if !bytes.is_empty() {
+            return Ok(bytes);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+    Ok(fs::metadata(path)?.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(path: &Path) -> io::Result<bool> {
+    let metadata = fs::metadata(path)?;
+    Ok(!metadata.is_file() && !metadata.is_dir())
+}
+
+/// Keeps reading images from the named pipe at `path` on a thread of its
+/// own, delivering each as `UserEvent::ImageChanged`. Stops when the pipe
+/// can no longer be read.
+pub fn watch(
+    path: PathBuf,
+    source_alpha: AlphaMode,
+    max_bytes: u64,
+    proxy: EventLoopProxy<UserEvent>,
+) {
+    thread::Builder::new()
+        .name("fifo".to_owned())
+        .spawn(move || {
+            loop {
+                // a broken image is reported and skipped, a broken pipe ends
+                // the watch
+                let (image, broken) = match read_bytes(&path) {
+                    Ok(bytes) => (
+                        decoder::decode_bytes(&bytes, source_alpha, max_bytes),
+                        false,
+                    ),
+                    Err(e) => (Err(e.into()), true),
+                };
+                let event = UserEvent::ImageChanged {
+                    path: path.clone(),
+                    image,
+                };
+                if proxy.send_event(event).is_err() || broken {
+                    return;
+                }
+            }
+        })
+        .expect("Failed to spawn fifo thread.");
+}
@@ -0,0 +1,31 @@
+use glam::Vec2;
+use serde::Deserialize;
+
+/// How an image is scaled to the window. Aspect ratio is always preserved.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FitMode {
+    /// Whole image visible, letterboxed if needed.
+    #[default]
+    Fit,
+    /// Window fully covered, image cropped if needed.
+    Fill,
+    /// One image pixel per window pixel.
+    ActualSize,
+    FitWidth,
+    FitHeight,
+}
+
+impl FitMode {
+    pub fn zoom(self, image_size: Vec2, viewport: Vec2) -> f32 {
+        let scale = viewport / image_size;
+
+        match self {
+            FitMode::Fit => scale.min_element(),
+            FitMode::Fill => scale.max_element(),
+            FitMode::ActualSize => 1.0,
+            FitMode::FitWidth => scale.x,
+            FitMode::FitHeight => scale.y,
+        }
+    }
+}
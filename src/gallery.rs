@@ -0,0 +1,111 @@
+use crate::constants::{GALLERY_CELL_SIZE, GALLERY_GAP};
+use glam::{Vec2, vec2};
+use std::ops::Range;
+
+/// Grid of thumbnails for every image in the playlist, laid out row by row
+/// and scrolled vertically. Positions are in window pixels.
+pub struct Gallery {
+    selected: usize,
+    /// Distance scrolled down from the first row.
+    scroll: f32,
+}
+
+impl Gallery {
+    pub fn new(selected: usize) -> Self {
+        Self {
+            selected,
+            scroll: 0.0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn columns(viewport: Vec2) -> usize {
+        (((viewport.x - GALLERY_GAP) / (GALLERY_CELL_SIZE + GALLERY_GAP)) as usize).max(1)
+    }
+
+    /// Minimum and maximum corner of the cell for image `index`. The grid is
+    /// centered horizontally.
+    pub fn cell_rect(&self, index: usize, viewport: Vec2) -> (Vec2, Vec2) {
+        let columns = Self::columns(viewport);
+        let stride = GALLERY_CELL_SIZE + GALLERY_GAP;
+        let width = columns as f32 * stride - GALLERY_GAP;
+        let left = ((viewport.x - width) / 2.0).max(GALLERY_GAP);
+
+        let column = (index % columns) as f32;
+        let row = (index / columns) as f32;
+        let min = vec2(
+            left + column * stride,
+            GALLERY_GAP + row * stride - self.scroll,
+        );
+        (min, min + GALLERY_CELL_SIZE)
+    }
+
+    /// Images with a cell at least partly on screen.
+    pub fn visible(&self, len: usize, viewport: Vec2) -> Range<usize> {
+        let columns = Self::columns(viewport);
+        let stride = GALLERY_CELL_SIZE + GALLERY_GAP;
+        let first_row = ((self.scroll - GALLERY_GAP) / stride).floor().max(0.0) as usize;
+        let last_row = ((self.scroll + viewport.y) / stride).ceil() as usize;
+        (first_row * columns).min(len)..(last_row * columns).min(len)
+    }
+
+    /// Most images `visible` can return for `viewport`, wherever it is
+    /// scrolled.
+    pub fn max_visible(viewport: Vec2) -> usize {
+        let stride = GALLERY_CELL_SIZE + GALLERY_GAP;
+        let rows = ((viewport.y + GALLERY_GAP) / stride).ceil() as usize + 1;
+        Self::columns(viewport) * rows
+    }
+
+    /// Image whose cell is under `position`, if any.
+    pub fn index_at(&self, position: Vec2, len: usize, viewport: Vec2) -> Option<usize> {
+        self.visible(len, viewport).find(|&i| {
+            let (min, max) = self.cell_rect(i, viewport);
+            position.cmpge(min).all() && position.cmplt(max).all()
+        })
+    }
+
+    /// Selects image `index`, clamped to the playlist, and scrolls it into view.
+    pub fn select(&mut self, index: isize, len: usize, viewport: Vec2) {
+        self.selected = index.clamp(0, len as isize - 1) as usize;
+
+        let (min, max) = self.cell_rect(self.selected, viewport);
+        if min.y < GALLERY_GAP {
+            self.scroll -= GALLERY_GAP - min.y;
+        } else if max.y > viewport.y - GALLERY_GAP {
+            self.scroll += max.y - (viewport.y - GALLERY_GAP);
+        }
+        self.scroll_by(0.0, len, viewport);
+    }
+
+    /// Moves the selection by `offset` images, stopping at either end.
+    pub fn move_selection(&mut self, offset: isize, len: usize, viewport: Vec2) {
+        self.select(self.selected as isize + offset, len, viewport);
+    }
+
+    /// Moves the selection by `rows` whole rows, stopping at the first or
+    /// last row. The last row may end before the selected column.
+    pub fn move_rows(&mut self, rows: isize, len: usize, viewport: Vec2) {
+        let columns = Self::columns(viewport) as isize;
+        let last_row = (len as isize - 1) / columns;
+        let selected = self.selected as isize;
+        let row = (selected / columns + rows).clamp(0, last_row);
+        self.select(row * columns + selected % columns, len, viewport);
+    }
+
+    /// Rows that fit on screen, for paging.
+    pub fn page_rows(viewport: Vec2) -> isize {
+        ((viewport.y / (GALLERY_CELL_SIZE + GALLERY_GAP)) as isize).max(1)
+    }
+
+    /// Scrolls down by `delta` pixels, staying within the grid.
+    pub fn scroll_by(&mut self, delta: f32, len: usize, viewport: Vec2) {
+        let rows = len.div_ceil(Self::columns(viewport));
+        let height = rows as f32 * (GALLERY_CELL_SIZE + GALLERY_GAP) + GALLERY_GAP;
+        let max_scroll = (height - viewport.y).max(0.0);
+        self.scroll = (self.scroll + delta).clamp(0.0, max_scroll);
+    }
+}
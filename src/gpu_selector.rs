@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use std::ffi::CStr;
+
+/// Picks a physical device by its position in Vulkan's enumeration order, or
+/// by a case-insensitive part of its name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum GpuSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl GpuSelector {
+    pub fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => GpuSelector::Index(index),
+            Err(_) => GpuSelector::Name(value.to_owned()),
+        }
+    }
+
+    pub fn matches(&self, index: usize, name: &CStr) -> bool {
+        match self {
+            GpuSelector::Index(x) => *x == index,
+            GpuSelector::Name(x) => name
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&x.to_lowercase()),
+        }
+    }
+}
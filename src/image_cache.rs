@@ -0,0 +1,130 @@
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
+
+struct Entry<V> {
+    value: V,
+    size: u64,
+    last_used: u64,
+}
+
+/// Least-recently-used cache bounded by the total byte size of its values.
+///
+/// Evicted values are handed back to the caller, since GPU resources need a
+/// device to be released.
+pub struct LruCache<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    budget: u64,
+    used: u64,
+    tick: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> LruCache<K, V> {
+    pub fn new(budget: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget,
+            used: 0,
+            tick: 0,
+        }
+    }
+
+    /// Returns the value and marks it as most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.tick;
+        Some(&entry.value)
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Inserts `value` as most recently used, returning whatever had to make
+    /// room for it. The new value is kept even if it alone exceeds the budget.
+    pub fn insert(&mut self, key: K, value: V, size: u64) -> Vec<V> {
+        self.insert_keeping(key, value, size, |_| false)
+    }
+
+    /// Like `insert`, but never evicts the values whose keys `keep` returns
+    /// true for, even if that leaves the cache over budget.
+    pub fn insert_keeping(
+        &mut self,
+        key: K,
+        value: V,
+        size: u64,
+        keep: impl Fn(&K) -> bool,
+    ) -> Vec<V> {
+        self.tick += 1;
+        let mut evicted: Vec<V> = self.remove(&key).into_iter().collect();
+
+        self.used += size;
+        self.entries.insert(
+            key.clone(),
+            Entry {
+                value,
+                size,
+                last_used: self.tick,
+            },
+        );
+
+        while self.used > self.budget
+            && let Some(value) = self.pop_lru_except(|x| *x == key || keep(x))
+        {
+            evicted.push(value);
+        }
+
+        evicted
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.used -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Moves the value under `from` to `to`, keeping its place in the LRU
+    /// order. Returns the value it replaced.
+    pub fn rename(&mut self, from: &K, to: K) -> Option<V> {
+        let entry = self.entries.remove(from)?;
+        let replaced = self.remove(&to);
+        self.entries.insert(to, entry);
+        replaced
+    }
+
+    /// Removes the least recently used value whose key `keep` returns false
+    /// for, if any.
+    pub fn pop_lru_except(&mut self, keep: impl Fn(&K) -> bool) -> Option<V> {
+        let key = self
+            .entries
+            .iter()
+            .filter(|(key, _)| !keep(key))
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())?;
+        self.remove(&key)
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = V> + '_ {
+        self.used = 0;
+        self.entries.drain().map(|(_, entry)| entry.value)
+    }
+}
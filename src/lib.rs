@@ -1,32 +1,137 @@
+mod action;
+mod allocator;
+mod animation;
 mod app;
+mod background;
+mod bindings;
 mod buffer;
+mod camera;
+mod cli;
+mod color;
 mod command_pool;
+mod compare;
+mod config;
 mod constants;
 mod debug_messenger;
+mod decoder;
 mod descriptor_pool;
 mod descriptor_set_layout;
 mod device;
 mod engine;
 mod fence;
+mod fifo;
+mod fit_mode;
+mod gallery;
+mod gpu_selector;
+mod image_cache;
 mod instance;
+mod mesh;
+mod mipmap;
+mod orientation;
+mod paths;
 mod physical_device;
 mod pipeline;
+mod playlist;
+mod present_mode;
+mod push_constants;
 mod queue;
 mod render_pass;
+mod scale_filter;
 mod semaphore;
+mod settings;
 mod shader_module;
+mod slideshow;
 mod surface;
 mod swapchain;
+mod texture;
+mod texture_upload;
+mod thumbnail;
+mod thumbnail_atlas;
+mod thumbnail_cache;
+mod tiled_texture;
+mod transition;
 mod uniform_buffer_object;
+mod user_event;
 mod vertex;
+mod watcher;
+mod window_geometry;
+
+pub use cli::{Cli, Command, USAGE};
 
 use app::App;
+use config::Config;
+use playlist::Playlist;
+use std::error::Error;
+use user_event::UserEvent;
 use winit::event_loop::{ControlFlow, EventLoop};
 
-pub fn run() {
-    let event_loop = EventLoop::new().expect("Failed to create event loop.");
-    event_loop.set_control_flow(ControlFlow::Poll);
+pub fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    // a config asked for by name has to exist, the default one doesn't
+    if let Some(path) = &cli.config
+        && !path.is_file()
+    {
+        return Err(format!("{}: no such config file", path.display()).into());
+    }
+    let Config {
+        mut settings,
+        bindings,
+    } = match cli.config.clone().or_else(Config::path) {
+        Some(path) => {
+            let (config, errors) = Config::load(&path);
+            for error in errors {
+                eprintln!("{}: {error}", path.display());
+            }
+            config
+        }
+        None => Config::default(),
+    };
+    cli.apply(&mut settings);
+
+    // the pipe is the whole playlist, and only its watcher may read from it
+    let mut playlist = match &cli.watch_fifo {
+        Some(path) => Playlist::single(path.clone()),
+        None => Playlist::from_paths(&cli.paths, settings.recursive, settings.sort)?,
+    };
+    if cli.shuffle {
+        playlist.shuffle();
+    }
+    if let Some(target) = &cli.start_at
+        && !playlist.start_at(target)
+    {
+        eprintln!("--start-at: no image `{target}` in the playlist");
+    }
+    let image = match &cli.watch_fifo {
+        Some(path) => fifo::read_image(path, settings.source_alpha, settings.cache_size_bytes()),
+        None => decoder::decode(
+            playlist.current(),
+            settings.source_alpha,
+            settings.cache_size_bytes(),
+        ),
+    }
+    .map_err(|e| format!("{}: {e}", playlist.current().display()))?;
+
+    let event_loop = EventLoop::<UserEvent>::with_user_event()
+        .build()
+        .expect("Failed to create event loop.");
+    event_loop.set_control_flow(ControlFlow::Wait);
 
-    let mut app = App::new(unsafe { ash::Entry::load().unwrap() });
+    let proxy = event_loop.create_proxy();
+    if let Some(path) = cli.watch_fifo {
+        fifo::watch(
+            path,
+            settings.source_alpha,
+            settings.cache_size_bytes(),
+            proxy.clone(),
+        );
+    }
+    let mut app = App::new(
+        unsafe { ash::Entry::load().unwrap() },
+        settings,
+        bindings,
+        playlist,
+        image,
+        proxy,
+    );
     event_loop.run_app(&mut app).unwrap();
+    Ok(())
 }
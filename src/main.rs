@@ -1,5 +1,25 @@
-use image_viewer::run;
+use image_viewer::{Cli, Command, USAGE, run};
+use std::{env, process};
 
 fn main() {
-    run();
+    let cli = match Cli::parse(env::args_os().skip(1)) {
+        Ok(Command::Run(cli)) => cli,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("image-viewer {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => {
+            eprintln!("image-viewer: {e}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(cli) {
+        eprintln!("image-viewer: {e}");
+        process::exit(1);
+    }
 }
@@ -0,0 +1,99 @@
+use crate::{allocator::Allocator, buffer::Buffer, constants::QUAD_INDICES, vertex::Vertex};
+use ash::vk;
+use glam::{Vec2, vec2};
+use std::error::Error;
+
+/// Axis-aligned rectangle and the part of a texture stretched over it.
+#[derive(Debug, Clone, Copy)]
+pub struct Quad {
+    pub pos_min: Vec2,
+    pub pos_max: Vec2,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+}
+
+/// Quads sharing one host visible buffer, vertices first and indices after.
+/// Quad `i` is drawn with `QUAD_INDICES.len()` indices starting at
+/// `i * QUAD_INDICES.len()`.
+pub struct Mesh {
+    buffer: Buffer,
+    index_offset: vk::DeviceSize,
+}
+
+impl Quad {
+    fn vertices(&self) -> [Vertex; 4] {
+        let (p0, p1) = (self.pos_min, self.pos_max);
+        let (t0, t1) = (self.uv_min, self.uv_max);
+
+        [
+            Vertex { pos: p0, uv: t0 },
+            Vertex {
+                pos: vec2(p1.x, p0.y),
+                uv: vec2(t1.x, t0.y),
+            },
+            Vertex { pos: p1, uv: t1 },
+            Vertex {
+                pos: vec2(p0.x, p1.y),
+                uv: vec2(t0.x, t1.y),
+            },
+        ]
+    }
+}
+
+impl Mesh {
+    pub fn new(
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        quads: &[Quad],
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let vertices: Vec<Vertex> = quads.iter().flat_map(Quad::vertices).collect();
+        let indices: Vec<u32> = (0..quads.len().try_into()?)
+            .flat_map(|i: u32| QUAD_INDICES.map(|x| x + 4 * i))
+            .collect();
+
+        let vertices_size = size_of_val(vertices.as_slice());
+        let indices_size = size_of_val(indices.as_slice());
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size((vertices_size + indices_size).try_into()?)
+            .usage(vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::INDEX_BUFFER)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = Buffer::new(
+            device,
+            allocator,
+            &buffer_info,
+            allocation_callbacks,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+
+        // vertices are 16 bytes, so the indices stay 4 byte aligned
+        unsafe {
+            let ptr = buffer.ptr().unwrap().cast::<u8>();
+            ptr.copy_from(vertices.as_ptr().cast(), vertices_size);
+            ptr.add(vertices_size)
+                .copy_from(indices.as_ptr().cast(), indices_size);
+        }
+
+        Ok(Self {
+            buffer,
+            index_offset: vertices_size.try_into()?,
+        })
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        self.buffer.cleanup(device, allocator, allocation_callbacks);
+    }
+
+    pub fn buffer(&self) -> vk::Buffer {
+        self.buffer.buffer()
+    }
+
+    pub fn index_offset(&self) -> vk::DeviceSize {
+        self.index_offset
+    }
+}
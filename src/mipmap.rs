@@ -0,0 +1,457 @@
+use crate::{
+    constants::MAX_MIPMAP_DESCRIPTOR_SETS, descriptor_pool::DescriptorPool,
+    descriptor_set_layout::DescriptorSetLayout, pipeline::Pipeline, shader_module::ShaderModule,
+    texture::Texture,
+};
+use ash::prelude::*;
+use ash::vk;
+
+/// Work group size of the downsampling shader along each axis.
+const WORK_GROUP_SIZE: u32 = 8;
+
+/// Compute pipeline downsampling mip levels, for texture formats that cannot
+/// be blitted with linear filtering.
+pub struct MipmapGenerator {
+    set_layout: DescriptorSetLayout,
+    descriptor_pool: DescriptorPool,
+    pipeline: Pipeline,
+}
+
+/// Per-level views and descriptor sets used while generating one texture's
+/// mip chain. They must outlive the command buffer that uses them.
+pub struct MipChain {
+    views: Vec<vk::ImageView>,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+/// Number of levels down to 1x1.
+pub fn mip_levels(extent: vk::Extent2D) -> u32 {
+    u32::BITS - extent.width.max(extent.height).leading_zeros()
+}
+
+/// Records blits filling every level of every layer of `texture` from the
+/// one above. Every level must be in `TRANSFER_DST_OPTIMAL` and all are left
+/// in `SHADER_READ_ONLY_OPTIMAL`.
+pub fn record_blits(device: &ash::Device, command_buffer: vk::CommandBuffer, texture: &Texture) {
+    let image = texture.image();
+    let last_level = texture.mip_levels() - 1;
+    let layer_count = texture.layer_count();
+    let mut size = vk::Offset3D {
+        x: texture.extent().width.try_into().unwrap(),
+        y: texture.extent().height.try_into().unwrap(),
+        z: 1,
+    };
+
+    for level in 1..=last_level {
+        let next_size = vk::Offset3D {
+            x: (size.x / 2).max(1),
+            y: (size.y / 2).max(1),
+            z: 1,
+        };
+        let blit = vk::ImageBlit::default()
+            .src_subresource(subresource_layers(level - 1, layer_count))
+            .src_offsets([vk::Offset3D::default(), size])
+            .dst_subresource(subresource_layers(level, layer_count))
+            .dst_offsets([vk::Offset3D::default(), next_size]);
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[level_barrier(image, level - 1)
+                    .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_READ)],
+            );
+            device.cmd_blit_image(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                vk::Filter::LINEAR,
+            );
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[level_barrier(image, level - 1)
+                    .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+                    .dst_access_mask(vk::AccessFlags::SHADER_READ)],
+            );
+        }
+
+        size = next_size;
+    }
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[level_barrier(image, last_level)
+                .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)],
+        );
+    }
+}
+
+impl MipmapGenerator {
+    pub fn new(
+        device: &ash::Device,
+        shader_code: &[u8],
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> VkResult<Self> {
+        let bindings = [
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(0)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::COMPUTE),
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(1)
+                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::COMPUTE),
+        ];
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        let set_layout =
+            unsafe { DescriptorSetLayout::new(device, &layout_info, allocation_callbacks)? };
+
+        let pool_sizes = [
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_count(MAX_MIPMAP_DESCRIPTOR_SETS),
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::STORAGE_IMAGE)
+                .descriptor_count(MAX_MIPMAP_DESCRIPTOR_SETS),
+        ];
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+            .max_sets(MAX_MIPMAP_DESCRIPTOR_SETS)
+            .pool_sizes(&pool_sizes);
+        let descriptor_pool =
+            unsafe { DescriptorPool::new(device, &pool_info, allocation_callbacks)? };
+
+        let set_layouts = [set_layout.layout()];
+        let layout_info = vk::PipelineLayoutCreateInfo::default().set_layouts(&set_layouts);
+        let layout = unsafe { device.create_pipeline_layout(&layout_info, allocation_callbacks)? };
+
+        let shader_module = ShaderModule::new(device, shader_code, allocation_callbacks)?;
+        let stage_info = vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(shader_module.module())
+            .name(c"main");
+        let pipeline_info = vk::ComputePipelineCreateInfo::default()
+            .stage(stage_info)
+            .layout(layout);
+        let pipeline = unsafe {
+            device
+                .create_compute_pipelines(
+                    vk::PipelineCache::null(),
+                    &[pipeline_info],
+                    allocation_callbacks,
+                )
+                .map_err(|(_, e)| e)?[0]
+        };
+
+        Ok(Self {
+            set_layout,
+            descriptor_pool,
+            pipeline: Pipeline::from(layout, pipeline),
+        })
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        unsafe {
+            self.pipeline.cleanup(device, allocation_callbacks);
+            self.descriptor_pool.cleanup(device, allocation_callbacks);
+            self.set_layout.cleanup(device, allocation_callbacks);
+        }
+    }
+
+    /// Creates the level views and allocates the descriptor sets for
+    /// generating the mip chain of `texture`, failing when the pool is
+    /// exhausted or a view can't be created.
+    pub fn allocate(&self, device: &ash::Device, texture: &Texture) -> VkResult<MipChain> {
+        let mip_levels = texture.mip_levels();
+        let mut chain = MipChain {
+            views: Vec::new(),
+            descriptor_sets: Vec::new(),
+        };
+        for level in 1..mip_levels {
+            match level_views(device, texture.image(), level) {
+                Ok(views) => chain.views.extend(views),
+                Err(e) => {
+                    chain.cleanup(device, self.descriptor_pool(), None);
+                    return Err(e);
+                }
+            }
+        }
+        if mip_levels > 1 {
+            let layouts = vec![self.set_layout.layout(); (mip_levels - 1).try_into().unwrap()];
+            let alloc_info = vk::DescriptorSetAllocateInfo::default()
+                .descriptor_pool(self.descriptor_pool.pool())
+                .set_layouts(&layouts);
+            match unsafe { device.allocate_descriptor_sets(&alloc_info) } {
+                Ok(sets) => chain.descriptor_sets = sets,
+                Err(e) => {
+                    chain.cleanup(device, self.descriptor_pool(), None);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Records compute dispatches filling every level of every layer of
+    /// `texture` from the one above, using `chain` from `allocate`. Every level must be in
+    /// `TRANSFER_DST_OPTIMAL` and all are left in `SHADER_READ_ONLY_OPTIMAL`.
+    ///
+    /// The texture must have been created with `MUTABLE_FORMAT` and `STORAGE`
+    /// usage: levels are written through `R8G8B8A8_UNORM` views, since sRGB
+    /// formats rarely support storage, and the shader encodes sRGB itself.
+    pub fn record(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        texture: &Texture,
+        chain: &MipChain,
+    ) {
+        let image = texture.image();
+        let last_level = texture.mip_levels() - 1;
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline.pipeline(),
+            );
+        }
+
+        let mut extent = texture.extent();
+        let levels = chain.views.chunks(2).zip(&chain.descriptor_sets);
+        for (level, (views, &set)) in (1..=last_level).zip(levels) {
+            extent = vk::Extent2D {
+                width: (extent.width / 2).max(1),
+                height: (extent.height / 2).max(1),
+            };
+
+            // texels are fetched and averaged in the shader, as the format may
+            // not support linear filtering
+            let src_infos = [vk::DescriptorImageInfo::default()
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .image_view(views[0])
+                .sampler(texture.nearest_sampler())];
+            let dst_infos = [vk::DescriptorImageInfo::default()
+                .image_layout(vk::ImageLayout::GENERAL)
+                .image_view(views[1])];
+            let desc_writes = [
+                vk::WriteDescriptorSet::default()
+                    .dst_set(set)
+                    .dst_binding(0)
+                    .descriptor_count(1)
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .image_info(&src_infos),
+                vk::WriteDescriptorSet::default()
+                    .dst_set(set)
+                    .dst_binding(1)
+                    .descriptor_count(1)
+                    .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                    .image_info(&dst_infos),
+            ];
+
+            // the first level was written by the copy, the others by the
+            // previous dispatch
+            let src_layout = if level == 1 {
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL
+            } else {
+                vk::ImageLayout::GENERAL
+            };
+            let barriers = [
+                level_barrier(image, level - 1)
+                    .old_layout(src_layout)
+                    .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .src_access_mask(
+                        vk::AccessFlags::TRANSFER_WRITE | vk::AccessFlags::SHADER_WRITE,
+                    )
+                    .dst_access_mask(vk::AccessFlags::SHADER_READ),
+                level_barrier(image, level)
+                    .old_layout(vk::ImageLayout::UNDEFINED)
+                    .new_layout(vk::ImageLayout::GENERAL)
+                    .src_access_mask(vk::AccessFlags::NONE)
+                    .dst_access_mask(vk::AccessFlags::SHADER_WRITE),
+            ];
+
+            unsafe {
+                device.update_descriptor_sets(&desc_writes, &[]);
+                device.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::TRANSFER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::COMPUTE_SHADER
+                        | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &barriers,
+                );
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    self.pipeline.layout(),
+                    0,
+                    &[set],
+                    &[],
+                );
+                device.cmd_dispatch(
+                    command_buffer,
+                    extent.width.div_ceil(WORK_GROUP_SIZE),
+                    extent.height.div_ceil(WORK_GROUP_SIZE),
+                    texture.layer_count(),
+                );
+            }
+        }
+
+        let (last_layout, last_access, last_stage) = if last_level == 0 {
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::PipelineStageFlags::TRANSFER,
+            )
+        } else {
+            (
+                vk::ImageLayout::GENERAL,
+                vk::AccessFlags::SHADER_WRITE,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+            )
+        };
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                last_stage,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[level_barrier(image, last_level)
+                    .old_layout(last_layout)
+                    .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .src_access_mask(last_access)
+                    .dst_access_mask(vk::AccessFlags::SHADER_READ)],
+            );
+        }
+    }
+
+    pub fn descriptor_pool(&self) -> vk::DescriptorPool {
+        self.descriptor_pool.pool()
+    }
+}
+
+impl MipChain {
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        unsafe {
+            if !self.descriptor_sets.is_empty() {
+                device
+                    .free_descriptor_sets(descriptor_pool, &self.descriptor_sets)
+                    .unwrap();
+            }
+            for view in self.views {
+                device.destroy_image_view(view, allocation_callbacks);
+            }
+        }
+    }
+}
+
+fn subresource_layers(level: u32, layer_count: u32) -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(level)
+        .base_array_layer(0)
+        .layer_count(layer_count)
+}
+
+/// One level of every layer.
+fn subresource_range(level: u32) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(level)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(vk::REMAINING_ARRAY_LAYERS)
+}
+
+fn level_barrier(image: vk::Image, level: u32) -> vk::ImageMemoryBarrier<'static> {
+    vk::ImageMemoryBarrier::default()
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range(level))
+}
+
+/// Views reading the level above `level` as sRGB and writing `level` as
+/// storage.
+fn level_views(device: &ash::Device, image: vk::Image, level: u32) -> VkResult<[vk::ImageView; 2]> {
+    let src_view = level_view(
+        device,
+        image,
+        level - 1,
+        vk::Format::R8G8B8A8_SRGB,
+        vk::ImageUsageFlags::SAMPLED,
+    )?;
+    match level_view(
+        device,
+        image,
+        level,
+        vk::Format::R8G8B8A8_UNORM,
+        vk::ImageUsageFlags::STORAGE,
+    ) {
+        Ok(dst_view) => Ok([src_view, dst_view]),
+        Err(e) => {
+            unsafe { device.destroy_image_view(src_view, None) };
+            Err(e)
+        }
+    }
+}
+
+fn level_view(
+    device: &ash::Device,
+    image: vk::Image,
+    level: u32,
+    format: vk::Format,
+    usage: vk::ImageUsageFlags,
+) -> VkResult<vk::ImageView> {
+    let mut usage_info = vk::ImageViewUsageCreateInfo::default().usage(usage);
+    let view_info = vk::ImageViewCreateInfo::default()
+        .push_next(&mut usage_info)
+        .image(image)
+        .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+        .format(format)
+        .subresource_range(subresource_range(level));
+
+    unsafe { device.create_image_view(&view_info, None) }
+}
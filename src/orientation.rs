@@ -0,0 +1,111 @@
+use glam::{Mat4, Vec2, vec2, vec3};
+use image::{RgbaImage, imageops};
+use std::{
+    f32::consts::FRAC_PI_2,
+    io::{BufRead, Seek},
+};
+
+/// One of the eight ways to lay an image on screen: mirrored horizontally if
+/// `flipped`, then rotated clockwise by `quarter_turns`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Orientation {
+    quarter_turns: u32,
+    flipped: bool,
+}
+
+impl Orientation {
+    /// Maps the EXIF/TIFF orientation tag to the transform that displays the
+    /// image upright. Unknown values are left alone.
+    pub fn from_exif(value: u32) -> Self {
+        let (quarter_turns, flipped) = match value {
+            2 => (0, true),
+            3 => (2, false),
+            4 => (2, true),
+            5 => (3, true),
+            6 => (1, false),
+            7 => (1, true),
+            8 => (3, false),
+            _ => (0, false),
+        };
+        Self {
+            quarter_turns,
+            flipped,
+        }
+    }
+
+    /// Reads the orientation tag of an image file held in `reader`.
+    pub fn read_from(reader: &mut (impl BufRead + Seek)) -> Self {
+        let Ok(exif) = exif::Reader::new().read_from_container(reader) else {
+            return Self::default();
+        };
+
+        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|x| x.value.get_uint(0))
+            .map_or_else(Self::default, Self::from_exif)
+    }
+
+    /// Lays out `image` the way it is displayed.
+    pub fn apply(&self, image: RgbaImage) -> RgbaImage {
+        let image = if self.flipped {
+            imageops::flip_horizontal(&image)
+        } else {
+            image
+        };
+        match self.quarter_turns {
+            1 => imageops::rotate90(&image),
+            2 => imageops::rotate180(&image),
+            3 => imageops::rotate270(&image),
+            _ => image,
+        }
+    }
+
+    pub fn rotate_cw(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 1) % 4,
+            ..self
+        }
+    }
+
+    pub fn rotate_ccw(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 3) % 4,
+            ..self
+        }
+    }
+
+    /// Mirrors the image as currently shown left to right. Mirroring before a
+    /// rotation turns it the other way, hence the negated turns.
+    pub fn flip_horizontal(self) -> Self {
+        Self {
+            quarter_turns: (4 - self.quarter_turns) % 4,
+            flipped: !self.flipped,
+        }
+    }
+
+    /// Mirrors the image as currently shown top to bottom, which is a
+    /// horizontal flip followed by a half turn.
+    pub fn flip_vertical(self) -> Self {
+        let flipped = self.flip_horizontal();
+        Self {
+            quarter_turns: (flipped.quarter_turns + 2) % 4,
+            ..flipped
+        }
+    }
+
+    /// Size of an image of `size` once laid out on screen.
+    pub fn apply_size(self, size: Vec2) -> Vec2 {
+        if self.quarter_turns % 2 == 1 {
+            vec2(size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    /// Transform in image-centered pixels, y pointing down.
+    pub fn matrix(self) -> Mat4 {
+        let mirror = if self.flipped { -1.0 } else { 1.0 };
+
+        Mat4::from_rotation_z(self.quarter_turns as f32 * FRAC_PI_2)
+            * Mat4::from_scale(vec3(mirror, 1.0, 1.0))
+    }
+}
@@ -0,0 +1,29 @@
+use std::{env, path::PathBuf};
+
+/// Base directory from the XDG base directory variable `var`, falling back to
+/// `fallback` under the home directory.
+fn xdg_base(var: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => Some(PathBuf::from(env::var_os("HOME")?).join(fallback)),
+    }
+}
+
+/// Per-user directory of this application under an XDG base directory.
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    Some(xdg_base(var, fallback)?.join("image-viewer"))
+}
+
+/// Where state that should survive restarts, like window geometry, is kept.
+pub fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Thumbnails shared with file managers and other viewers.
+pub fn thumbnail_dir() -> Option<PathBuf> {
+    Some(xdg_base("XDG_CACHE_HOME", ".cache")?.join("thumbnails"))
+}
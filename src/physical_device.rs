@@ -33,12 +33,12 @@ impl PhysicalDevice {
         let mut required_extension_names: Vec<&CStr> = unsafe {
             required_extension_names
                 .iter()
-                .map(|x| CStr::from_ptr(x.clone()))
+                .map(|x| CStr::from_ptr(*x))
                 .collect()
         };
         required_extension_names.retain(|x| !supported_extension_names.contains(x));
 
-        Ok(required_extension_names.len() == 0)
+        Ok(required_extension_names.is_empty())
     }
 
     pub fn query_extension_properties(
@@ -85,6 +85,18 @@ impl PhysicalDevice {
         }
     }
 
+    pub fn query_properties(&self, vk_instance: &ash::Instance) -> vk::PhysicalDeviceProperties {
+        unsafe { vk_instance.get_physical_device_properties(self.device()) }
+    }
+
+    pub fn query_format_properties(
+        &self,
+        vk_instance: &ash::Instance,
+        format: vk::Format,
+    ) -> vk::FormatProperties {
+        unsafe { vk_instance.get_physical_device_format_properties(self.device(), format) }
+    }
+
     pub fn query_features(&self, vk_instance: &ash::Instance) -> vk::PhysicalDeviceFeatures {
         unsafe { vk_instance.get_physical_device_features(self.device()) }
     }
@@ -121,4 +133,23 @@ impl PhysicalDevice {
     ) -> vk::PhysicalDeviceMemoryProperties {
         unsafe { vk_instance.get_physical_device_memory_properties(self.device()) }
     }
+
+    /// Memory properties along with the current budget and usage of each heap.
+    /// Requires `VK_EXT_memory_budget`.
+    pub fn query_memory_budget(
+        &self,
+        vk_instance: &ash::Instance,
+    ) -> (
+        vk::PhysicalDeviceMemoryProperties,
+        vk::PhysicalDeviceMemoryBudgetPropertiesEXT<'static>,
+    ) {
+        let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget);
+        unsafe {
+            vk_instance.get_physical_device_memory_properties2(self.device(), &mut properties)
+        };
+        let memory_properties = properties.memory_properties;
+
+        (memory_properties, budget)
+    }
 }
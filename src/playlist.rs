@@ -0,0 +1,351 @@
+use crate::decoder::STDIN_PATH;
+use serde::Deserialize;
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fs, io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    /// File name with digit runs compared by value, so `2.png` < `10.png`.
+    #[default]
+    Natural,
+    Name,
+    Modified,
+    Size,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub reverse: bool,
+}
+
+/// Ordered list of images and the position of the one being shown.
+pub struct Playlist {
+    paths: Vec<PathBuf>,
+    index: usize,
+    /// Directories whose images are all in the playlist, as opposed to files
+    /// given one by one.
+    listed_dirs: HashSet<PathBuf>,
+}
+
+impl Playlist {
+    /// Builds a playlist from command line paths. A single file brings in
+    /// the images next to it, directories bring in the images inside them,
+    /// and several files make up the playlist on their own, in the order given.
+    /// Standard input has no neighbors, so it is always taken on its own.
+    pub fn from_paths(paths: &[PathBuf], recursive: bool, order: SortOrder) -> io::Result<Self> {
+        if let [path] = paths
+            && !path.is_dir()
+            && path.as_os_str() != STDIN_PATH
+        {
+            return Self::from_file(path, order);
+        }
+
+        let mut all = Vec::new();
+        let mut listed_dirs = HashSet::new();
+        for path in paths {
+            if path.is_dir() {
+                all.extend(read_dir_sorted(path, recursive, order, &mut listed_dirs)?);
+            } else {
+                all.push(path.clone());
+            }
+        }
+
+        if all.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no images found"));
+        }
+        Ok(Self {
+            paths: all,
+            index: 0,
+            listed_dirs,
+        })
+    }
+
+    /// Builds a playlist of `path` alone.
+    pub fn single(path: PathBuf) -> Self {
+        Self {
+            paths: vec![path],
+            index: 0,
+            listed_dirs: HashSet::new(),
+        }
+    }
+
+    /// Builds a playlist of every supported image next to `path`, starting at `path`.
+    pub fn from_file(path: &Path, order: SortOrder) -> io::Result<Self> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut listed_dirs = HashSet::new();
+        let mut paths = read_dir_sorted(dir, false, order, &mut listed_dirs)?;
+        let index = match paths.iter().position(|x| x.file_name() == path.file_name()) {
+            Some(index) => index,
+            // keep an explicitly requested file even when its extension is unknown
+            None => {
+                paths.insert(0, dir.join(path.file_name().unwrap_or_default()));
+                0
+            }
+        };
+
+        Ok(Self {
+            paths,
+            index,
+            listed_dirs,
+        })
+    }
+
+    /// Moves to the image named `target`, or at the 1-based position it
+    /// holds. Returns whether it was found.
+    pub fn start_at(&mut self, target: &str) -> bool {
+        let index = match target.parse::<usize>() {
+            Ok(position) if (1..=self.len()).contains(&position) => Some(position - 1),
+            _ => self
+                .paths
+                .iter()
+                .position(|x| x.as_os_str() == target || x.file_name() == Some(target.as_ref())),
+        };
+
+        if let Some(index) = index {
+            self.index = index;
+        }
+        index.is_some()
+    }
+
+    /// Randomizes the order, keeping the current image current.
+    pub fn shuffle(&mut self) {
+        let current = self.paths.swap_remove(self.index);
+        fastrand::shuffle(&mut self.paths);
+        self.paths.insert(0, current);
+        self.index = 0;
+    }
+
+    /// Whether every image in `dir` is in the playlist, rather than only
+    /// files given one by one.
+    pub fn lists(&self, dir: &Path) -> bool {
+        self.listed_dirs.contains(dir)
+    }
+
+    /// Reads a listed directory again, putting its images where they were.
+    pub fn rescan(&mut self, dir: &Path, order: SortOrder) -> io::Result<()> {
+        let fresh = read_dir_sorted(dir, false, order, &mut HashSet::new())?;
+        let start = self
+            .paths
+            .iter()
+            .position(|x| x.parent() == Some(dir))
+            .unwrap_or(self.len());
+
+        self.update(|paths| {
+            paths.retain(|x| x.parent() != Some(dir));
+            paths.splice(start..start, fresh);
+        });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.update(|paths| paths.retain(|x| x != path));
+    }
+
+    /// Replaces `from` with `to` in place.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        self.update(|paths| {
+            for path in paths.iter_mut().filter(|x| *x == from) {
+                *path = to.to_path_buf();
+            }
+        });
+    }
+
+    /// Changes the paths with `f`, keeping the current image current or
+    /// moving to whichever took its place when it is gone. A change that
+    /// would leave nothing to show is dropped.
+    fn update(&mut self, f: impl FnOnce(&mut Vec<PathBuf>)) {
+        let mut paths = self.paths.clone();
+        f(&mut paths);
+        if paths.is_empty() {
+            return;
+        }
+
+        self.index = paths
+            .iter()
+            .position(|x| x == self.current())
+            .unwrap_or(self.index.min(paths.len() - 1));
+        self.paths = paths;
+    }
+
+    pub fn current(&self) -> &Path {
+        &self.paths[self.index]
+    }
+
+    pub fn get(&self, index: usize) -> &Path {
+        &self.paths[index]
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.paths.iter().position(|x| x == path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Path `offset` away from the current one, wrapping around at either end.
+    pub fn peek(&self, offset: isize) -> &Path {
+        let len = self.len() as isize;
+        &self.paths[(self.index as isize + offset).rem_euclid(len) as usize]
+    }
+
+    /// Moves by `offset`, wrapping around at either end.
+    pub fn step(&mut self, offset: isize) {
+        let len = self.len() as isize;
+        self.index = (self.index as isize + offset).rem_euclid(len) as usize;
+    }
+
+    /// Moves by `offset`, stopping at the first or last image.
+    pub fn jump(&mut self, offset: isize) {
+        let last = self.len() as isize - 1;
+        self.index = (self.index as isize + offset).clamp(0, last) as usize;
+    }
+
+    /// Moves to image `index`, clamped to the last one.
+    pub fn go_to(&mut self, index: usize) {
+        self.index = index.min(self.len() - 1);
+    }
+
+    pub fn first(&mut self) {
+        self.index = 0;
+    }
+
+    pub fn last(&mut self) {
+        self.index = self.len() - 1;
+    }
+}
+
+/// Supported images in `dir`, sorted by `order`. Subdirectories follow the
+/// images, each in natural order, when `recursive`. Symlinked files are
+/// included. Every directory read is added to `listed_dirs`.
+fn read_dir_sorted(
+    dir: &Path,
+    recursive: bool,
+    order: SortOrder,
+    listed_dirs: &mut HashSet<PathBuf>,
+) -> io::Result<Vec<PathBuf>> {
+    listed_dirs.insert(dir.to_path_buf());
+    let mut entries = Vec::new();
+    let mut subdirs = Vec::new();
+    // an entry that vanished or can't be read is left out rather than
+    // failing the whole listing
+    for entry in fs::read_dir(dir)?.flatten() {
+        let entry_path = entry.path();
+        // follows symlinks, unlike `DirEntry::metadata`
+        let Ok(metadata) = fs::metadata(&entry_path) else {
+            continue;
+        };
+        if metadata.is_dir() && recursive {
+            // linked directories aren't descended into, as they may loop back
+            if entry.file_type().is_ok_and(|x| !x.is_symlink()) {
+                subdirs.push(entry_path);
+            }
+        } else if metadata.is_file() && is_supported(&entry_path) {
+            entries.push((entry_path, metadata));
+        }
+    }
+
+    entries.sort_by(|(a, a_meta), (b, b_meta)| {
+        let ordering = match order.key {
+            SortKey::Natural => natural_cmp(&file_name(a), &file_name(b)),
+            SortKey::Name => a.file_name().cmp(&b.file_name()),
+            SortKey::Modified => a_meta.modified().ok().cmp(&b_meta.modified().ok()),
+            SortKey::Size => a_meta.len().cmp(&b_meta.len()),
+        }
+        .then_with(|| a.cmp(b));
+
+        if order.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let mut paths: Vec<PathBuf> = entries.into_iter().map(|(path, _)| path).collect();
+    subdirs.sort_by(|a, b| natural_cmp(&file_name(a), &file_name(b)));
+    for subdir in subdirs {
+        // one unreadable subdirectory shouldn't lose the rest
+        match read_dir_sorted(&subdir, recursive, order, listed_dirs) {
+            Ok(subdir_paths) => paths.extend(subdir_paths),
+            Err(e) => eprintln!("{}: {e}", subdir.display()),
+        }
+    }
+    Ok(paths)
+}
+
+pub fn is_supported(path: &Path) -> bool {
+    image::ImageFormat::from_path(path).is_ok_and(|format| format.reading_enabled())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Compares strings case-insensitively, treating runs of digits as numbers.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        let ordering = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_digits(&mut a);
+                let y = take_digits(&mut b);
+                let x_value = x.trim_start_matches('0');
+                let y_value = y.trim_start_matches('0');
+
+                x_value
+                    .len()
+                    .cmp(&y_value.len())
+                    .then_with(|| x_value.cmp(y_value))
+                    // fewer leading zeros first
+                    .then_with(|| x.len().cmp(&y.len()))
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                a.next();
+                b.next();
+                ordering
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
@@ -0,0 +1,28 @@
+use ash::vk;
+use serde::Deserialize;
+
+/// How frames are queued for the display.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentMode {
+    /// Waits for vertical blank. Always available and easiest on power.
+    #[default]
+    Fifo,
+    /// Like `fifo`, but shows a late frame right away, which may tear.
+    FifoRelaxed,
+    /// Replaces the queued frame with newer ones without tearing.
+    Mailbox,
+    /// Shows frames as soon as they are done, which may tear.
+    Immediate,
+}
+
+impl PresentMode {
+    pub fn to_vk(self) -> vk::PresentModeKHR {
+        match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
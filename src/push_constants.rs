@@ -0,0 +1,48 @@
+/// Per-draw parameters for the image shaders.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PushConstants {
+    pub scale_filter: i32,
+    /// Samples without filtering when nonzero.
+    pub pixel_art: u32,
+    /// Outlines each texel when nonzero.
+    pub pixel_grid: u32,
+    pub background: i32,
+    /// Checkerboard square size in window pixels.
+    pub checker_size: f32,
+    /// Which of the uniform buffer's transforms places the image.
+    pub transform: u32,
+    pub opacity: f32,
+    /// Horizontal shift in window widths.
+    pub offset: f32,
+    /// Layer of the texture holding the frame to draw.
+    pub layer: u32,
+}
+
+impl PushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts((self as *const Self).cast(), size_of::<Self>()) }
+    }
+}
+
+/// Per-draw parameters for the overlay shaders, which draw one rectangle.
+#[repr(C)]
+#[derive(Debug)]
+pub struct OverlayPushConstants {
+    /// Minimum and maximum corner in window pixels.
+    pub rect: [f32; 4],
+    /// Premultiplied linear color inside the border.
+    pub fill: [f32; 4],
+    /// Premultiplied linear color of the border.
+    pub border: [f32; 4],
+    /// Window size in pixels.
+    pub viewport: [f32; 2],
+    /// Border width in window pixels, inside the rectangle.
+    pub border_width: f32,
+}
+
+impl OverlayPushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts((self as *const Self).cast(), size_of::<Self>()) }
+    }
+}
@@ -3,16 +3,29 @@ use ash::vk;
 pub struct Queues {
     pub graphics: vk::Queue,
     pub present: vk::Queue,
+    /// Same as `graphics` when the device has no transfer-only family.
+    pub transfer: vk::Queue,
 }
 
 #[derive(Default)]
 pub struct QueueFamilyIndices {
     pub graphics_family: Option<u32>,
     pub present_family: Option<u32>,
+    /// A family with TRANSFER but neither GRAPHICS nor COMPUTE, if any.
+    pub transfer_family: Option<u32>,
 }
 
 impl QueueFamilyIndices {
     pub fn is_complete(&self) -> bool {
         self.graphics_family.is_some() && self.present_family.is_some()
     }
+
+    /// Family that texture uploads are recorded on.
+    pub fn upload_family(&self) -> Option<u32> {
+        self.transfer_family.or(self.graphics_family)
+    }
+
+    pub fn has_dedicated_transfer(&self) -> bool {
+        self.transfer_family.is_some() && self.transfer_family != self.graphics_family
+    }
 }
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// How textures are resampled when drawn. The discriminants are shared with
+/// the fragment shader.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[repr(i32)]
+pub enum ScaleFilter {
+    /// Hardware filtering across mip levels. Fastest.
+    #[default]
+    Trilinear = 0,
+    /// Mitchell-Netravali cubic, sharper when zoomed out.
+    Bicubic = 1,
+    /// Two-lobed Lanczos, sharpest but may ring around hard edges.
+    Lanczos = 2,
+}
+
+impl ScaleFilter {
+    pub fn next(self) -> Self {
+        match self {
+            ScaleFilter::Trilinear => ScaleFilter::Bicubic,
+            ScaleFilter::Bicubic => ScaleFilter::Lanczos,
+            ScaleFilter::Lanczos => ScaleFilter::Trilinear,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    background::Background, color::AlphaMode, compare::CompareMode, constants::MAX_COMPARED_IMAGES,
+    fit_mode::FitMode, gpu_selector::GpuSelector, playlist::SortOrder, present_mode::PresentMode,
+    scale_filter::ScaleFilter, transition::Transition,
+};
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// User-tunable behavior, shared by the app and the engine.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub fit_mode: FitMode,
+    /// Zoom for the first image instead of its fit.
+    pub zoom: Option<f32>,
+    pub sort: SortOrder,
+    /// Includes images in subdirectories of directories given or dropped.
+    pub recursive: bool,
+    pub scale_filter: ScaleFilter,
+    /// Unfiltered sampling with whole zoom factors, for inspecting pixel art.
+    pub pixel_art: bool,
+    /// Outlines each image pixel in pixel art mode once zoomed in far enough.
+    pub pixel_grid: bool,
+    /// Starts in borderless fullscreen.
+    pub fullscreen: bool,
+    /// Resizes the window to each image's natural size.
+    pub fit_window: bool,
+    pub background: Background,
+    /// sRGB color around the image, and behind it with a solid background.
+    pub background_color: [u8; 3],
+    /// Side of a checkerboard square in window pixels.
+    pub checker_size: u32,
+    /// How images store alpha, since most formats don't say.
+    pub source_alpha: AlphaMode,
+    /// Budget for decoded pixels in RAM, and separately for textures in VRAM.
+    pub cache_size_mb: u64,
+    /// Images decoded ahead on each side of the current one.
+    pub prefetch: usize,
+    /// Reloads images and the playlist as files change on disk.
+    pub live_reload: bool,
+    /// Times animations play before stopping on their last frame, or 0 to
+    /// loop forever. Unset, each plays as often as its file asks.
+    pub loop_count: Option<u32>,
+    /// Starts with the slideshow running. Configs from before this setting
+    /// existed start it by setting `slideshow_interval` alone.
+    pub slideshow: bool,
+    /// Seconds each image stays up in the slideshow.
+    #[serde(deserialize_with = "positive_seconds")]
+    pub slideshow_interval: f64,
+    /// Goes through the images in random order, each once before any repeats.
+    pub slideshow_shuffle: bool,
+    /// Starts over after the last image instead of stopping.
+    pub slideshow_loop: bool,
+    pub transition: Transition,
+    /// Seconds a transition between slideshow images takes.
+    #[serde(deserialize_with = "seconds")]
+    pub transition_duration: f64,
+    /// Starts comparing the current image with the ones after it.
+    pub compare: bool,
+    pub compare_mode: CompareMode,
+    /// Images compared side by side, from 2 to 4.
+    #[serde(deserialize_with = "compare_count")]
+    pub compare_count: usize,
+    /// Physical device to render with, otherwise the last suitable one.
+    pub gpu: Option<GpuSelector>,
+    pub present_mode: PresentMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fit_mode: FitMode::default(),
+            zoom: None,
+            sort: SortOrder::default(),
+            recursive: false,
+            scale_filter: ScaleFilter::default(),
+            pixel_art: false,
+            pixel_grid: true,
+            fullscreen: false,
+            fit_window: false,
+            background: Background::default(),
+            background_color: [32, 32, 32],
+            checker_size: 16,
+            source_alpha: AlphaMode::default(),
+            cache_size_mb: 512,
+            prefetch: 2,
+            live_reload: true,
+            loop_count: None,
+            slideshow: false,
+            slideshow_interval: 5.0,
+            slideshow_shuffle: false,
+            slideshow_loop: true,
+            transition: Transition::default(),
+            transition_duration: 0.5,
+            compare: false,
+            compare_mode: CompareMode::default(),
+            compare_count: 2,
+            gpu: None,
+            present_mode: PresentMode::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn cache_size_bytes(&self) -> u64 {
+        self.cache_size_mb * 1024 * 1024
+    }
+}
+
+/// Reads a finite number of seconds that isn't negative.
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let seconds = f64::deserialize(deserializer)?;
+    if !(seconds.is_finite() && seconds >= 0.0) {
+        return Err(D::Error::custom(format!(
+            "expected a finite number of seconds, not negative, got {seconds}"
+        )));
+    }
+    Ok(seconds)
+}
+
+/// Reads a finite number of seconds above zero.
+fn positive_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let seconds = seconds(deserializer)?;
+    if seconds == 0.0 {
+        return Err(D::Error::custom("expected more than 0 seconds"));
+    }
+    Ok(seconds)
+}
+
+/// Reads how many images to compare, which the panes have room for.
+fn compare_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let count = usize::deserialize(deserializer)?;
+    if !(2..=MAX_COMPARED_IMAGES).contains(&count) {
+        return Err(D::Error::custom(format!(
+            "expected 2 to {MAX_COMPARED_IMAGES} images, got {count}"
+        )));
+    }
+    Ok(count)
+}
@@ -10,12 +10,14 @@ pub struct ShaderModule<'a> {
 impl<'a> ShaderModule<'a> {
     pub fn new(
         device: &'a ash::Device,
-        code: &Vec<u8>,
+        code: &[u8],
         allocation_callbacks: Option<&'a vk::AllocationCallbacks>,
     ) -> VkResult<Self> {
-        let mut module_info = vk::ShaderModuleCreateInfo::default();
-        module_info.code_size = code.len();
-        module_info.p_code = code.as_ptr() as *const u32;
+        let module_info = vk::ShaderModuleCreateInfo {
+            code_size: code.len(),
+            p_code: code.as_ptr() as *const u32,
+            ..Default::default()
+        };
 
         let module = unsafe { device.create_shader_module(&module_info, allocation_callbacks)? };
         Ok(Self {
@@ -0,0 +1,173 @@
+use crate::playlist::Playlist;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Whether the slideshow is running, and the order it goes through the
+/// playlist in.
+#[derive(Debug)]
+pub struct Slideshow {
+    paused: bool,
+    /// Images shown this round when shuffling.
+    shown: HashSet<PathBuf>,
+    /// Image picked to come next when shuffling, kept so it can be decoded
+    /// ahead.
+    upcoming: Option<PathBuf>,
+}
+
+impl Slideshow {
+    pub fn new(paused: bool) -> Self {
+        Self {
+            paused,
+            shown: HashSet::new(),
+            upcoming: None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Forgets which images were shown, starting a new round.
+    pub fn restart(&mut self) {
+        self.shown.clear();
+        self.upcoming = None;
+    }
+
+    /// Image the slideshow moves to after the current one, or `None` once a
+    /// slideshow that doesn't loop has shown them all. Shuffled, it is picked
+    /// at random from those not shown yet this round, and a new round never
+    /// starts with the image just shown.
+    pub fn upcoming(
+        &mut self,
+        playlist: &Playlist,
+        shuffle: bool,
+        repeat: bool,
+    ) -> Option<PathBuf> {
+        if !shuffle {
+            let last = playlist.index() + 1 == playlist.len();
+            return (repeat || !last).then(|| playlist.peek(1).to_path_buf());
+        }
+
+        let current = playlist.current();
+        self.shown.insert(current.to_path_buf());
+        if let Some(upcoming) = &self.upcoming
+            && upcoming != current
+            && playlist.position(upcoming).is_some()
+        {
+            return Some(upcoming.clone());
+        }
+
+        let mut left = self.not_shown(playlist);
+        if left.is_empty() {
+            if !repeat {
+                return None;
+            }
+            self.restart();
+            self.shown.insert(current.to_path_buf());
+            left = self.not_shown(playlist);
+        }
+        // a playlist of one image shows it again
+        let upcoming = fastrand::choice(left).unwrap_or(current);
+        self.upcoming = Some(upcoming.to_path_buf());
+        self.upcoming.clone()
+    }
+
+    fn not_shown<'a>(&self, playlist: &'a Playlist) -> Vec<&'a Path> {
+        playlist
+            .paths()
+            .iter()
+            .filter(|x| !self.shown.contains(*x))
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist::SortOrder;
+
+    fn playlist(len: usize) -> Playlist {
+        let paths: Vec<_> = (0..len)
+            .map(|i| PathBuf::from(format!("{i}.png")))
+            .collect();
+        Playlist::from_paths(&paths, false, SortOrder::default()).unwrap()
+    }
+
+    fn go_to(playlist: &mut Playlist, path: &Path) {
+        playlist.go_to(playlist.position(path).unwrap());
+    }
+
+    #[test]
+    fn in_order_moves_to_the_next_image() {
+        let mut playlist = playlist(3);
+        let mut slideshow = Slideshow::new(false);
+
+        assert_eq!(
+            slideshow.upcoming(&playlist, false, false),
+            Some(PathBuf::from("1.png"))
+        );
+        playlist.last();
+        assert_eq!(slideshow.upcoming(&playlist, false, false), None);
+        assert_eq!(
+            slideshow.upcoming(&playlist, false, true),
+            Some(PathBuf::from("0.png"))
+        );
+    }
+
+    #[test]
+    fn shuffle_shows_each_image_once_per_round() {
+        let mut playlist = playlist(5);
+        let mut slideshow = Slideshow::new(false);
+        let mut shown = HashSet::from([playlist.current().to_path_buf()]);
+
+        while let Some(path) = slideshow.upcoming(&playlist, true, false) {
+            assert!(shown.insert(path.clone()), "{path:?} shown twice");
+            go_to(&mut playlist, &path);
+        }
+        assert_eq!(shown.len(), 5);
+    }
+
+    #[test]
+    fn shuffle_keeps_its_pick_until_moving_on() {
+        let playlist = playlist(5);
+        let mut slideshow = Slideshow::new(false);
+
+        let upcoming = slideshow.upcoming(&playlist, true, true);
+        assert_eq!(slideshow.upcoming(&playlist, true, true), upcoming);
+    }
+
+    #[test]
+    fn shuffle_never_repeats_across_rounds() {
+        let mut playlist = playlist(3);
+        let mut slideshow = Slideshow::new(false);
+
+        for _ in 0..100 {
+            let path = slideshow.upcoming(&playlist, true, true).unwrap();
+            assert_ne!(path, playlist.current());
+            go_to(&mut playlist, &path);
+        }
+    }
+
+    #[test]
+    fn shuffle_repeats_a_lone_image() {
+        let playlist = Playlist::single(PathBuf::from("0.png"));
+        let mut slideshow = Slideshow::new(false);
+
+        assert_eq!(
+            slideshow.upcoming(&playlist, true, true),
+            Some(PathBuf::from("0.png"))
+        );
+        assert_eq!(slideshow.upcoming(&playlist, true, false), None);
+    }
+}
@@ -6,6 +6,7 @@ pub struct Swapchain {
     swapchain: vk::SwapchainKHR,
     format: vk::Format,
     extent: vk::Extent2D,
+    composite_alpha: vk::CompositeAlphaFlagsKHR,
     image_views: Vec<vk::ImageView>,
     framebuffers: Option<Vec<vk::Framebuffer>>,
 }
@@ -19,6 +20,7 @@ impl Swapchain {
     ) -> VkResult<Self> {
         let format = swapchain_info.image_format;
         let extent = swapchain_info.image_extent;
+        let composite_alpha = swapchain_info.composite_alpha;
         let device = khr::swapchain::Device::new(vk_instance, vk_device);
         let swapchain = unsafe { device.create_swapchain(swapchain_info, allocator)? };
         let images = unsafe { device.get_swapchain_images(swapchain)? };
@@ -53,6 +55,7 @@ impl Swapchain {
             swapchain,
             format,
             extent,
+            composite_alpha,
             image_views,
             framebuffers: None,
         })
@@ -112,7 +115,7 @@ impl Swapchain {
             }
         }
 
-        return available_formats[0];
+        available_formats[0]
     }
 
     pub fn choose_extent(
@@ -139,6 +142,26 @@ impl Swapchain {
         }
     }
 
+    pub fn choose_composite_alpha(
+        supported: vk::CompositeAlphaFlagsKHR,
+        preferred: vk::CompositeAlphaFlagsKHR,
+    ) -> vk::CompositeAlphaFlagsKHR {
+        if supported.contains(preferred) {
+            return preferred;
+        }
+
+        // at least one bit is guaranteed to be set
+        [
+            vk::CompositeAlphaFlagsKHR::OPAQUE,
+            vk::CompositeAlphaFlagsKHR::INHERIT,
+            vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+            vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+        ]
+        .into_iter()
+        .find(|x| supported.contains(*x))
+        .unwrap()
+    }
+
     pub fn choose_present_mode(
         available_modes: Vec<vk::PresentModeKHR>,
         preferred_mode: vk::PresentModeKHR,
@@ -180,6 +203,10 @@ impl Swapchain {
         self.extent
     }
 
+    pub fn composite_alpha(&self) -> vk::CompositeAlphaFlagsKHR {
+        self.composite_alpha
+    }
+
     pub fn framebuffers(&self) -> Option<&Vec<vk::Framebuffer>> {
         self.framebuffers.as_ref()
     }
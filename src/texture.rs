@@ -0,0 +1,185 @@
+use crate::allocator::{Allocation, Allocator};
+use ash::vk;
+use std::error::Error;
+
+/// A sampled image, viewed as an array so animation frames can share one
+/// texture a layer each.
+#[derive(Debug)]
+pub struct Texture {
+    extent: vk::Extent2D,
+    mip_levels: u32,
+    layer_count: u32,
+    image: vk::Image,
+    allocation: Allocation,
+    view: vk::ImageView,
+    sampler: vk::Sampler,
+    nearest_sampler: vk::Sampler,
+}
+
+impl Texture {
+    pub fn new(
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        image_info: &vk::ImageCreateInfo,
+        sampler_info: &vk::SamplerCreateInfo,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let image = unsafe { device.create_image(image_info, allocation_callbacks)? };
+        let mem_requirements = unsafe { device.get_image_memory_requirements(image) };
+
+        let allocation = match allocator.allocate(
+            device,
+            mem_requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            image_info.tiling == vk::ImageTiling::LINEAR,
+            allocation_callbacks,
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                unsafe { device.destroy_image(image, allocation_callbacks) };
+                return Err(e);
+            }
+        };
+        let views = Self::create_views(
+            device,
+            image,
+            &allocation,
+            image_info,
+            sampler_info,
+            allocation_callbacks,
+        );
+        let (view, sampler, nearest_sampler) = match views {
+            Ok(x) => x,
+            Err(e) => {
+                unsafe { device.destroy_image(image, allocation_callbacks) };
+                allocator.free(device, allocation, allocation_callbacks);
+                return Err(e.into());
+            }
+        };
+
+        Ok(Self {
+            extent: vk::Extent2D {
+                width: image_info.extent.width,
+                height: image_info.extent.height,
+            },
+            mip_levels: image_info.mip_levels,
+            layer_count: image_info.array_layers,
+            image,
+            allocation,
+            view,
+            sampler,
+            nearest_sampler,
+        })
+    }
+
+    /// Binds `image` to its memory and creates its view and samplers,
+    /// destroying whatever was created when a step fails.
+    fn create_views(
+        device: &ash::Device,
+        image: vk::Image,
+        allocation: &Allocation,
+        image_info: &vk::ImageCreateInfo,
+        sampler_info: &vk::SamplerCreateInfo,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> Result<(vk::ImageView, vk::Sampler, vk::Sampler), vk::Result> {
+        unsafe { device.bind_image_memory(image, allocation.memory(), allocation.offset())? };
+
+        // the image may also allow storage through views of another format
+        let mut view_usage_info =
+            vk::ImageViewUsageCreateInfo::default().usage(vk::ImageUsageFlags::SAMPLED);
+        let swizzle_identity = vk::ComponentSwizzle::IDENTITY;
+        let view_info = vk::ImageViewCreateInfo::default()
+            .push_next(&mut view_usage_info)
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+            .format(image_info.format)
+            .components(
+                vk::ComponentMapping::default()
+                    .r(swizzle_identity)
+                    .g(swizzle_identity)
+                    .b(swizzle_identity)
+                    .a(swizzle_identity),
+            )
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .base_mip_level(0)
+                    .level_count(image_info.mip_levels)
+                    .base_array_layer(0)
+                    .layer_count(image_info.array_layers),
+            );
+        let view = unsafe { device.create_image_view(&view_info, allocation_callbacks)? };
+        let sampler = match unsafe { device.create_sampler(sampler_info, allocation_callbacks) } {
+            Ok(x) => x,
+            Err(e) => {
+                unsafe { device.destroy_image_view(view, allocation_callbacks) };
+                return Err(e);
+            }
+        };
+
+        // same addressing and LOD range, unfiltered for pixel art
+        let nearest_sampler_info = sampler_info
+            .mag_filter(vk::Filter::NEAREST)
+            .min_filter(vk::Filter::NEAREST)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .anisotropy_enable(false);
+        match unsafe { device.create_sampler(&nearest_sampler_info, allocation_callbacks) } {
+            Ok(nearest_sampler) => Ok((view, sampler, nearest_sampler)),
+            Err(e) => {
+                unsafe {
+                    device.destroy_sampler(sampler, allocation_callbacks);
+                    device.destroy_image_view(view, allocation_callbacks);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        unsafe {
+            device.destroy_sampler(self.sampler(), allocation_callbacks);
+            device.destroy_sampler(self.nearest_sampler(), allocation_callbacks);
+            device.destroy_image_view(self.view(), allocation_callbacks);
+            device.destroy_image(self.image(), allocation_callbacks);
+        }
+        allocator.free(device, self.allocation, allocation_callbacks);
+    }
+
+    /// Bytes of device memory backing the image.
+    pub fn size(&self) -> vk::DeviceSize {
+        self.allocation.size()
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    pub fn layer_count(&self) -> u32 {
+        self.layer_count
+    }
+
+    pub fn image(&self) -> vk::Image {
+        self.image
+    }
+
+    pub fn view(&self) -> vk::ImageView {
+        self.view
+    }
+
+    pub fn sampler(&self) -> vk::Sampler {
+        self.sampler
+    }
+
+    pub fn nearest_sampler(&self) -> vk::Sampler {
+        self.nearest_sampler
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    allocator::Allocator,
+    buffer::Buffer,
+    fence::Fence,
+    mipmap::{MipChain, MipmapGenerator},
+    semaphore::Semaphore,
+    tiled_texture::TiledTexture,
+};
+use ash::prelude::*;
+use ash::vk;
+use glam::UVec2;
+
+/// A texture whose pixels are still being copied in. The tiles must not be
+/// sampled, and none of this may be freed, until the fence is signaled.
+pub struct TextureUpload {
+    texture: TiledTexture,
+    staging_buffer: Buffer,
+    /// Recorded command buffers with the pools they were allocated from.
+    command_buffers: Vec<(vk::CommandPool, vk::CommandBuffer)>,
+    /// Orders the graphics queue's ownership acquire after the transfer queue's release.
+    semaphore: Option<Semaphore>,
+    fence: Fence,
+    /// Left over from the compute fallback for mip generation.
+    mip_chains: Vec<MipChain>,
+}
+
+/// Thumbnails being copied back to back into a device local buffer on the
+/// upload queue, to be copied into the atlas from there. Nothing may be read
+/// or freed until the fence is signaled.
+pub struct ThumbnailUpload {
+    buffer: Buffer,
+    staging_buffer: Buffer,
+    /// Recorded command buffer with the pool it was allocated from.
+    command_buffer: (vk::CommandPool, vk::CommandBuffer),
+    fence: Fence,
+    /// Size of each thumbnail, in the order they are in `buffer`.
+    sizes: Vec<UVec2>,
+}
+
+impl TextureUpload {
+    pub fn new(
+        texture: TiledTexture,
+        staging_buffer: Buffer,
+        command_buffers: Vec<(vk::CommandPool, vk::CommandBuffer)>,
+        semaphore: Option<Semaphore>,
+        fence: Fence,
+        mip_chains: Vec<MipChain>,
+    ) -> Self {
+        Self {
+            texture,
+            staging_buffer,
+            command_buffers,
+            semaphore,
+            fence,
+            mip_chains,
+        }
+    }
+
+    pub fn is_complete(&self, device: &ash::Device) -> VkResult<bool> {
+        unsafe { device.get_fence_status(self.fence.fence()) }
+    }
+
+    pub fn wait(&self, device: &ash::Device) -> VkResult<()> {
+        unsafe { device.wait_for_fences(&[self.fence.fence()], true, u64::MAX) }
+    }
+
+    /// Frees the upload resources and hands back the texture. Only call this
+    /// once the upload is complete.
+    pub fn finish(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        mipmap_generator: Option<&MipmapGenerator>,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> TiledTexture {
+        unsafe {
+            for (pool, command_buffer) in self.command_buffers {
+                device.free_command_buffers(pool, &[command_buffer]);
+            }
+            if let Some(semaphore) = self.semaphore {
+                semaphore.cleanup(device, allocation_callbacks);
+            }
+            self.fence.cleanup(device, allocation_callbacks);
+        }
+        if let Some(generator) = mipmap_generator {
+            for chain in self.mip_chains {
+                chain.cleanup(device, generator.descriptor_pool(), allocation_callbacks);
+            }
+        }
+        self.staging_buffer
+            .cleanup(device, allocator, allocation_callbacks);
+
+        self.texture
+    }
+}
+
+impl ThumbnailUpload {
+    pub fn new(
+        buffer: Buffer,
+        staging_buffer: Buffer,
+        command_buffer: (vk::CommandPool, vk::CommandBuffer),
+        fence: Fence,
+        sizes: Vec<UVec2>,
+    ) -> Self {
+        Self {
+            buffer,
+            staging_buffer,
+            command_buffer,
+            fence,
+            sizes,
+        }
+    }
+
+    pub fn is_complete(&self, device: &ash::Device) -> VkResult<bool> {
+        unsafe { device.get_fence_status(self.fence.fence()) }
+    }
+
+    pub fn wait(&self, device: &ash::Device) -> VkResult<()> {
+        unsafe { device.wait_for_fences(&[self.fence.fence()], true, u64::MAX) }
+    }
+
+    pub fn sizes(&self) -> &[UVec2] {
+        &self.sizes
+    }
+
+    /// Frees the upload resources and hands back the buffer holding the
+    /// thumbnails. Only call this once the upload is complete.
+    pub fn finish(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) -> Buffer {
+        let (pool, command_buffer) = self.command_buffer;
+        unsafe {
+            device.free_command_buffers(pool, &[command_buffer]);
+            self.fence.cleanup(device, allocation_callbacks);
+        }
+        self.staging_buffer
+            .cleanup(device, allocator, allocation_callbacks);
+
+        self.buffer
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    color::{self, AlphaMode},
+    constants::THUMBNAIL_SIZE,
+    decoder, thumbnail_cache,
+};
+use image::{RgbaImage, imageops};
+use std::path::Path;
+
+/// Thumbnail of the image at `path` no larger than `THUMBNAIL_SIZE`, upright
+/// and premultiplied like full images. Comes from the shared thumbnail cache
+/// when it is up to date, and goes into it otherwise.
+pub fn generate(path: &Path, source_alpha: AlphaMode) -> image::ImageResult<RgbaImage> {
+    let mut thumbnail = match thumbnail_cache::load(path) {
+        Some(thumbnail) => thumbnail,
+        None => {
+            let (pixels, orientation) = decoder::decode_raw(path)?;
+            let thumbnail = orientation.apply(shrink(pixels));
+            if let Err(e) = thumbnail_cache::store(path, &thumbnail) {
+                eprintln!("{}: failed to cache thumbnail: {e}", path.display());
+            }
+            thumbnail
+        }
+    };
+
+    color::premultiply(&mut thumbnail, source_alpha);
+    Ok(thumbnail)
+}
+
+/// Scales `image` down to fit `THUMBNAIL_SIZE`, leaving smaller ones alone.
+pub fn shrink(image: RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let scale = THUMBNAIL_SIZE as f64 / width.max(height) as f64;
+    if scale >= 1.0 {
+        return image;
+    }
+    imageops::thumbnail(
+        &image,
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
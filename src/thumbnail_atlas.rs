@@ -0,0 +1,133 @@
+use crate::{
+    allocator::Allocator, constants::THUMBNAIL_SIZE, image_cache::LruCache, texture::Texture,
+};
+use ash::vk;
+use glam::{UVec2, Vec4, uvec2};
+use std::path::{Path, PathBuf};
+
+/// Cell of the atlas holding one thumbnail in its top left corner.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    index: u32,
+    size: UVec2,
+}
+
+/// One texture holding gallery thumbnails in a grid of `THUMBNAIL_SIZE`
+/// cells. The least recently drawn thumbnails make way once it is full.
+pub struct ThumbnailAtlas {
+    texture: Texture,
+    descriptor_set: vk::DescriptorSet,
+    /// Whether the texture has been cleared to transparent yet, which
+    /// happens in the first frame that draws from it.
+    cleared: bool,
+    columns: u32,
+    slots: LruCache<PathBuf, Slot>,
+    free: Vec<u32>,
+}
+
+impl Slot {
+    fn offset(&self, columns: u32) -> vk::Offset2D {
+        vk::Offset2D {
+            x: ((self.index % columns) * THUMBNAIL_SIZE)
+                .try_into()
+                .unwrap(),
+            y: ((self.index / columns) * THUMBNAIL_SIZE)
+                .try_into()
+                .unwrap(),
+        }
+    }
+}
+
+impl ThumbnailAtlas {
+    /// Takes over a square `texture` whose side is a multiple of
+    /// `THUMBNAIL_SIZE`.
+    pub fn new(texture: Texture, descriptor_set: vk::DescriptorSet) -> Self {
+        let columns = texture.extent().width / THUMBNAIL_SIZE;
+        let slot_count = columns * columns;
+        Self {
+            texture,
+            descriptor_set,
+            cleared: false,
+            columns,
+            // every slot weighs one
+            slots: LruCache::new(slot_count.into()),
+            free: (0..slot_count).rev().collect(),
+        }
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        descriptor_pool: vk::DescriptorPool,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        unsafe {
+            device
+                .free_descriptor_sets(descriptor_pool, &[self.descriptor_set])
+                .unwrap()
+        };
+        self.texture
+            .cleanup(device, allocator, allocation_callbacks);
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+
+    pub fn is_cleared(&self) -> bool {
+        self.cleared
+    }
+
+    pub fn set_cleared(&mut self) {
+        self.cleared = true;
+    }
+
+    /// How many thumbnails fit at once.
+    pub fn slot_count(&self) -> usize {
+        (self.columns * self.columns).try_into().unwrap()
+    }
+
+    /// Texture coordinates of the thumbnail for `path`, marking it as
+    /// recently used. Half a texel is left off each edge so filtering never
+    /// reaches into the neighboring cells.
+    pub fn uv_rect(&mut self, path: &Path) -> Option<(Vec4, UVec2)> {
+        let slot = *self.slots.get(&path.to_path_buf())?;
+        let offset = slot.offset(self.columns);
+        let min = uvec2(offset.x as u32, offset.y as u32).as_vec2() + 0.5;
+        let max = min + slot.size.as_vec2() - 1.0;
+        let rect = Vec4::new(min.x, min.y, max.x, max.y) / self.texture.extent().width as f32;
+        Some((rect, slot.size))
+    }
+
+    /// Reserves a cell for a thumbnail of `size` texels, replacing any
+    /// thumbnail already there for `path`, and returns where to copy it.
+    /// Thumbnails `keep` returns true for stay put, so there is no room when
+    /// it covers the whole atlas.
+    pub fn insert(
+        &mut self,
+        path: PathBuf,
+        size: UVec2,
+        keep: impl Fn(&PathBuf) -> bool,
+    ) -> Option<vk::Offset2D> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => self.slots.pop_lru_except(keep)?.index,
+        };
+        let slot = Slot { index, size };
+        for replaced in self.slots.insert(path, slot, 1) {
+            self.free.push(replaced.index);
+        }
+        Some(slot.offset(self.columns))
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        if let Some(slot) = self.slots.remove(&path.to_path_buf()) {
+            self.free.push(slot.index);
+        }
+    }
+}
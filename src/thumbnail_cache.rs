@@ -0,0 +1,212 @@
+use crate::{constants::THUMBNAIL_SIZE, paths, thumbnail};
+use image::{ImageFormat, RgbaImage};
+use md5::{Digest, Md5};
+use std::{
+    error::Error,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{BufWriter, Cursor},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, OpenOptionsExt},
+    },
+    path::Path,
+    process,
+    time::UNIX_EPOCH,
+};
+
+/// Subdirectories of the cache by the longest side of their thumbnails.
+const FLAVORS: [(&str, u32); 3] = [("normal", 128), ("large", 256), ("x-large", 512)];
+
+/// Where and under which keys a file's thumbnail is cached, following the
+/// freedesktop thumbnail spec.
+struct Entry {
+    uri: String,
+    mtime: u64,
+    file_name: String,
+}
+
+impl Entry {
+    fn of(path: &Path) -> Option<Self> {
+        // `..` and symlinks would give the same file another URI
+        let path = fs::canonicalize(path).ok()?;
+        let modified = fs::metadata(&path).ok()?.modified().ok()?;
+        let uri = file_uri(&path);
+
+        Some(Self {
+            file_name: file_name(&uri),
+            mtime: modified.duration_since(UNIX_EPOCH).ok()?.as_secs(),
+            uri,
+        })
+    }
+
+    /// Whether the PNG in `bytes` was made from this version of the file.
+    fn matches(&self, bytes: &[u8]) -> bool {
+        let Ok(reader) = png::Decoder::new(Cursor::new(bytes)).read_info() else {
+            return false;
+        };
+        let info = reader.info();
+        let text = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|x| (x.keyword.clone(), x.text.clone()))
+            .chain(
+                info.utf8_text
+                    .iter()
+                    .filter_map(|x| Some((x.keyword.clone(), x.get_text().ok()?))),
+            );
+
+        let (mut uri, mut mtime) = (false, false);
+        for (keyword, text) in text {
+            match keyword.as_str() {
+                "Thumb::URI" => uri = text == self.uri,
+                "Thumb::MTime" => mtime = text.parse() == Ok(self.mtime),
+                _ => (),
+            }
+        }
+        uri && mtime
+    }
+}
+
+/// Cached thumbnail of `path` no larger than `THUMBNAIL_SIZE`, unless the
+/// file changed since it was made. Larger flavors are shrunk to fit.
+pub fn load(path: &Path) -> Option<RgbaImage> {
+    let dir = paths::thumbnail_dir()?;
+    let entry = Entry::of(path)?;
+
+    FLAVORS
+        .iter()
+        .filter(|(_, size)| *size >= THUMBNAIL_SIZE)
+        .find_map(|(flavor, _)| {
+            let bytes = fs::read(dir.join(flavor).join(&entry.file_name)).ok()?;
+            if !entry.matches(&bytes) {
+                return None;
+            }
+            let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png).ok()?;
+            Some(thumbnail::shrink(image.into_rgba8()))
+        })
+}
+
+/// Caches `thumbnail` of `path`, straight alpha as the spec expects, in the
+/// smallest flavor that holds `THUMBNAIL_SIZE`. Files inside the cache are
+/// never thumbnailed themselves.
+pub fn store(path: &Path, thumbnail: &RgbaImage) -> Result<(), Box<dyn Error>> {
+    let dir = paths::thumbnail_dir().ok_or("no cache directory")?;
+    let Some(entry) = Entry::of(path) else {
+        return Ok(());
+    };
+    // the cache may not exist yet, and then holds no files either
+    let cache = fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone());
+    if fs::canonicalize(path)?.starts_with(cache) {
+        return Ok(());
+    }
+
+    let (flavor, _) = FLAVORS
+        .iter()
+        .find(|(_, size)| *size >= THUMBNAIL_SIZE)
+        .unwrap();
+    let dir = dir.join(flavor);
+    DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+
+    // written aside and renamed, so readers never see half a thumbnail
+    let target = dir.join(&entry.file_name);
+    let partial = dir.join(format!("{}.{}.tmp", entry.file_name, process::id()));
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&partial)?;
+
+    let result = write_png(BufWriter::new(file), &entry, thumbnail)
+        .and_then(|()| Ok(fs::rename(&partial, &target)?));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+fn write_png(
+    writer: BufWriter<fs::File>,
+    entry: &Entry,
+    thumbnail: &RgbaImage,
+) -> Result<(), Box<dyn Error>> {
+    let mut encoder = png::Encoder::new(writer, thumbnail.width(), thumbnail.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_text_chunk("Thumb::URI".to_owned(), entry.uri.clone())?;
+    encoder.add_text_chunk("Thumb::MTime".to_owned(), entry.mtime.to_string())?;
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(thumbnail.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Name of the cached thumbnail for `uri`, the hex MD5 of the URI.
+fn file_name(uri: &str) -> String {
+    let digest = Md5::digest(uri.as_bytes());
+    digest
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect::<String>()
+        + ".png"
+}
+
+/// `file://` URI of an absolute path, escaped like GLib does so the hash
+/// matches the one other applications compute.
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        let allowed = byte.is_ascii_alphanumeric() || b"!$&'()*+,=:@/-._~".contains(&byte);
+        if allowed {
+            uri.push(byte.into());
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // URIs as printed by `gio info`, which escapes with GLib
+
+    #[test]
+    fn file_uri_escapes_spaces() {
+        let uri = file_uri(Path::new("/tmp/My Pictures/photo 1.jpg"));
+        assert_eq!(uri, "file:///tmp/My%20Pictures/photo%201.jpg");
+        assert_eq!(file_name(&uri), "4752d3ddea3c17dd41509d59806c5320.png");
+    }
+
+    #[test]
+    fn file_uri_escapes_each_byte_of_non_ascii() {
+        let uri = file_uri(Path::new("/tmp/café.png"));
+        assert_eq!(uri, "file:///tmp/caf%C3%A9.png");
+        assert_eq!(file_name(&uri), "3ca5106e5bec26551ebb51fea11b2173.png");
+    }
+
+    #[test]
+    fn file_uri_escapes_reserved_characters() {
+        let uri = file_uri(Path::new("/tmp/a#b?c%d;e[f].png"));
+        assert_eq!(uri, "file:///tmp/a%23b%3Fc%25d%3Be%5Bf%5D.png");
+        assert_eq!(file_name(&uri), "bb267cb571f0088ca65ac0b9f8f7fe00.png");
+    }
+
+    #[test]
+    fn file_uri_keeps_characters_allowed_in_paths() {
+        let uri = file_uri(Path::new("/tmp/!$&'()*+,=:@-._~.png"));
+        assert_eq!(uri, "file:///tmp/!$&'()*+,=:@-._~.png");
+        assert_eq!(file_name(&uri), "7d0e8960dab98e91313d5597f8b725cc.png");
+    }
+
+    #[test]
+    fn file_name_matches_the_thumbnail_spec() {
+        // the example from the freedesktop thumbnail spec
+        assert_eq!(
+            file_name("file:///home/jens/photos/me.png"),
+            "c6ee772d9e49320e97ec29a7eb5b1697.png"
+        );
+    }
+}
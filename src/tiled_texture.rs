@@ -0,0 +1,247 @@
+use crate::{
+    allocator::Allocator,
+    constants::TILE_OVERLAP,
+    mesh::{Mesh, Quad},
+    texture::Texture,
+};
+use ash::vk;
+use glam::vec2;
+
+/// One texture of a tiled image and the descriptor set sampling it.
+pub struct Tile {
+    texture: Texture,
+    descriptor_set: vk::DescriptorSet,
+}
+
+/// An image split into textures no larger than the device allows, drawn as one
+/// quad per tile in the same -0.5..0.5 space as a single texture would be.
+pub struct TiledTexture {
+    extent: vk::Extent2D,
+    tiles: Vec<Tile>,
+    mesh: Mesh,
+}
+
+/// Texels of the image that go into one tile, and the quad it is drawn as.
+///
+/// Neighboring tiles reach `TILE_OVERLAP` texels past the edge they share, and
+/// each quad stops at the middle of the overlap. Filtering near a seam then reads the same texels
+/// on both sides as it would inside a single texture, so clamped sampling never
+/// shows a border. Tiles start at multiples of the overlap, which keeps mip
+/// levels seamless until the overlap shrinks below a texel.
+#[derive(Debug, Clone, Copy)]
+pub struct TileRegion {
+    pub rect: vk::Rect2D,
+    pub quad: Quad,
+}
+
+/// Span of one tile along an axis: `start..end` is drawn, and
+/// `texel_start..texel_end` is what the texture holds.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: u32,
+    end: u32,
+    texel_start: u32,
+    texel_end: u32,
+}
+
+impl Tile {
+    pub fn new(texture: Texture, descriptor_set: vk::DescriptorSet) -> Self {
+        Self {
+            texture,
+            descriptor_set,
+        }
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+}
+
+impl TiledTexture {
+    pub fn new(extent: vk::Extent2D, tiles: Vec<Tile>, mesh: Mesh) -> Self {
+        Self {
+            extent,
+            tiles,
+            mesh,
+        }
+    }
+
+    pub fn cleanup(
+        self,
+        device: &ash::Device,
+        allocator: &mut Allocator,
+        descriptor_pool: vk::DescriptorPool,
+        allocation_callbacks: Option<&vk::AllocationCallbacks>,
+    ) {
+        let descriptor_sets: Vec<_> = self.tiles.iter().map(Tile::descriptor_set).collect();
+        unsafe {
+            device
+                .free_descriptor_sets(descriptor_pool, &descriptor_sets)
+                .unwrap()
+        };
+        for tile in self.tiles {
+            tile.texture
+                .cleanup(device, allocator, allocation_callbacks);
+        }
+        self.mesh.cleanup(device, allocator, allocation_callbacks);
+    }
+
+    /// Size of the whole image.
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Images held, one per array layer of every tile.
+    pub fn layer_count(&self) -> u32 {
+        self.tiles[0].texture.layer_count()
+    }
+
+    /// Bytes of device memory taken by the tiles.
+    pub fn size(&self) -> vk::DeviceSize {
+        self.tiles.iter().map(|x| x.texture.size()).sum()
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+}
+
+/// Splits an image into tiles of at most `max_size` texels per side, row by
+/// row. Images that fit are a single tile covering the whole quad.
+pub fn layout(extent: vk::Extent2D, max_size: u32) -> Vec<TileRegion> {
+    let columns = spans(extent.width, max_size);
+    let rows = spans(extent.height, max_size);
+
+    let size = vec2(extent.width as f32, extent.height as f32);
+    rows.iter()
+        .flat_map(|row| columns.iter().map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let texel_min = vec2(column.texel_start as f32, row.texel_start as f32);
+            let texel_max = vec2(column.texel_end as f32, row.texel_end as f32);
+            let min = vec2(column.start as f32, row.start as f32);
+            let max = vec2(column.end as f32, row.end as f32);
+
+            TileRegion {
+                rect: vk::Rect2D {
+                    offset: vk::Offset2D {
+                        x: column.texel_start.try_into().unwrap(),
+                        y: row.texel_start.try_into().unwrap(),
+                    },
+                    extent: vk::Extent2D {
+                        width: column.texel_end - column.texel_start,
+                        height: row.texel_end - row.texel_start,
+                    },
+                },
+                quad: Quad {
+                    pos_min: min / size - 0.5,
+                    pos_max: max / size - 0.5,
+                    uv_min: (min - texel_min) / (texel_max - texel_min),
+                    uv_max: (max - texel_min) / (texel_max - texel_min),
+                },
+            }
+        })
+        .collect()
+}
+
+fn spans(len: u32, max_size: u32) -> Vec<Span> {
+    if len <= max_size {
+        return vec![Span {
+            start: 0,
+            end: len,
+            texel_start: 0,
+            texel_end: len,
+        }];
+    }
+
+    let step = max_size - 2 * TILE_OVERLAP;
+    (0..len)
+        .step_by(step.try_into().unwrap())
+        .map(|start| {
+            let end = (start + step).min(len);
+            Span {
+                start,
+                end,
+                texel_start: start.saturating_sub(TILE_OVERLAP),
+                texel_end: (end + TILE_OVERLAP).min(len),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_SIZE: u32 = 256;
+    const LENGTHS: [u32; 4] = [MAX_SIZE - 1, MAX_SIZE, MAX_SIZE + 1, MAX_SIZE * 5 + 7];
+
+    #[test]
+    fn spans_cover_the_image_without_gaps() {
+        for len in LENGTHS {
+            let spans = spans(len, MAX_SIZE);
+
+            assert_eq!(spans.first().unwrap().start, 0);
+            assert_eq!(spans.last().unwrap().end, len);
+            for pair in spans.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+            }
+            for span in &spans {
+                assert!(span.start < span.end);
+                assert!(span.texel_end - span.texel_start <= MAX_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn spans_overlap_on_inner_edges_only() {
+        for len in LENGTHS {
+            let spans = spans(len, MAX_SIZE);
+
+            assert_eq!(spans.len() == 1, len <= MAX_SIZE);
+            assert_eq!(spans.first().unwrap().texel_start, 0);
+            assert_eq!(spans.last().unwrap().texel_end, len);
+            for pair in spans.windows(2) {
+                assert_eq!(pair[0].texel_end, pair[0].end + TILE_OVERLAP);
+                assert_eq!(pair[1].texel_start, pair[1].start - TILE_OVERLAP);
+            }
+        }
+    }
+
+    #[test]
+    fn quads_meet_at_the_middle_of_the_overlap() {
+        for width in LENGTHS {
+            let extent = vk::Extent2D {
+                width,
+                height: MAX_SIZE / 2,
+            };
+            let regions = layout(extent, MAX_SIZE);
+
+            assert_eq!(regions.first().unwrap().quad.pos_min, vec2(-0.5, -0.5));
+            assert_eq!(regions.last().unwrap().quad.pos_max, vec2(0.5, 0.5));
+            for pair in regions.windows(2) {
+                let (left, right) = (pair[0], pair[1]);
+                assert_eq!(left.quad.pos_max.x, right.quad.pos_min.x);
+
+                let overlap_start = right.rect.offset.x as f32;
+                let overlap_end = (left.rect.offset.x as u32 + left.rect.extent.width) as f32;
+                let seam = (overlap_start + overlap_end) / 2.0;
+                assert!((left.quad.pos_max.x - (seam / width as f32 - 0.5)).abs() < 1e-6);
+
+                // both sides sample the seam at the same image texel
+                let texel = |region: TileRegion, u: f32| {
+                    region.rect.offset.x as f32 + u * region.rect.extent.width as f32
+                };
+                assert!((texel(left, left.quad.uv_max.x) - seam).abs() < 1e-3);
+                assert!((texel(right, right.quad.uv_min.x) - seam).abs() < 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Deserialize;
+
+/// How the slideshow replaces one image with the next.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// Switches at once.
+    None,
+    #[default]
+    Crossfade,
+    /// Pushes the outgoing image out of the window with the incoming one.
+    Slide,
+}
+
+/// How one of the two images of a transition is drawn. The two are added
+/// together, so their opacities should sum to one wherever they overlap.
+#[derive(Debug, Clone, Copy)]
+pub struct Layer {
+    pub opacity: f32,
+    /// Horizontal shift in window widths.
+    pub offset: f32,
+}
+
+impl Layer {
+    pub const OPAQUE: Self = Self {
+        opacity: 1.0,
+        offset: 0.0,
+    };
+}
+
+impl Transition {
+    /// How the outgoing and the incoming image are drawn `progress` of the
+    /// way through, where `direction` is -1 when going back through the
+    /// playlist and 1 otherwise.
+    pub fn layers(self, progress: f32, direction: f32) -> [Layer; 2] {
+        // eased in and out
+        let t = progress.clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+
+        match self {
+            Transition::None => [
+                Layer {
+                    opacity: 0.0,
+                    offset: 0.0,
+                },
+                Layer::OPAQUE,
+            ],
+            // halfway through, each image contributes half of the result
+            Transition::Crossfade => [
+                Layer {
+                    opacity: 1.0 - t,
+                    offset: 0.0,
+                },
+                Layer {
+                    opacity: t,
+                    offset: 0.0,
+                },
+            ],
+            Transition::Slide => [
+                Layer {
+                    opacity: 1.0,
+                    offset: -direction * t,
+                },
+                Layer {
+                    opacity: 1.0,
+                    offset: direction * (1.0 - t),
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opacities(layers: [Layer; 2]) -> [f32; 2] {
+        layers.map(|x| x.opacity)
+    }
+
+    fn offsets(layers: [Layer; 2]) -> [f32; 2] {
+        layers.map(|x| x.offset)
+    }
+
+    #[test]
+    fn none_shows_only_the_incoming_image() {
+        let layers = Transition::None.layers(0.3, 1.0);
+        assert_eq!(opacities(layers), [0.0, 1.0]);
+        assert_eq!(offsets(layers), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn crossfade_weights_add_up_to_one() {
+        assert_eq!(
+            opacities(Transition::Crossfade.layers(0.0, 1.0)),
+            [1.0, 0.0]
+        );
+        assert_eq!(
+            opacities(Transition::Crossfade.layers(0.5, 1.0)),
+            [0.5, 0.5]
+        );
+        assert_eq!(
+            opacities(Transition::Crossfade.layers(1.0, 1.0)),
+            [0.0, 1.0]
+        );
+        for i in 0..=10 {
+            let [from, to] = opacities(Transition::Crossfade.layers(i as f32 / 10.0, 1.0));
+            assert!((from + to - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn progress_is_clamped() {
+        for transition in [Transition::Crossfade, Transition::Slide] {
+            let before = transition.layers(-1.0, 1.0);
+            let start = transition.layers(0.0, 1.0);
+            assert_eq!(opacities(before), opacities(start));
+            assert_eq!(offsets(before), offsets(start));
+
+            let after = transition.layers(2.0, 1.0);
+            let end = transition.layers(1.0, 1.0);
+            assert_eq!(opacities(after), opacities(end));
+            assert_eq!(offsets(after), offsets(end));
+        }
+    }
+
+    #[test]
+    fn slide_pushes_in_the_direction_of_travel() {
+        for direction in [1.0, -1.0] {
+            let start = Transition::Slide.layers(0.0, direction);
+            assert_eq!(opacities(start), [1.0, 1.0]);
+            assert_eq!(offsets(start), [0.0, direction]);
+
+            let end = Transition::Slide.layers(1.0, direction);
+            assert_eq!(offsets(end), [-direction, 0.0]);
+        }
+        let [from, to] = offsets(Transition::Slide.layers(0.5, 1.0));
+        assert_eq!(to - from, 1.0);
+    }
+}
@@ -1,7 +1,20 @@
+use crate::constants::MAX_IMAGE_TRANSFORMS;
+use glam::Mat4;
+
+/// Places one image in the window.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub model: Mat4,
+    pub view: Mat4,
+    pub proj: Mat4,
+}
+
 #[repr(C, align(16))]
 #[derive(Debug)]
 pub struct UniformBufferObject {
-    pub model: glam::Mat4,
-    pub view: glam::Mat4,
-    pub proj: glam::Mat4,
+    /// One for each image drawn this frame: the compared images, or the
+    /// shown and the outgoing image of a transition. The gallery only uses
+    /// the projection of the first.
+    pub transforms: [Transform; MAX_IMAGE_TRANSFORMS],
 }
@@ -0,0 +1,22 @@
+use crate::{decoder::DecodedImage, watcher::FileChange};
+use std::path::PathBuf;
+
+/// Events sent to the event loop from other threads.
+pub enum UserEvent {
+    ImageDecoded {
+        path: PathBuf,
+        image: image::ImageResult<DecodedImage>,
+    },
+    /// A new version of an image that may already be cached or shown.
+    ImageChanged {
+        path: PathBuf,
+        image: image::ImageResult<DecodedImage>,
+    },
+    /// Thumbnail for the gallery, upright.
+    ThumbnailDecoded {
+        path: PathBuf,
+        image: image::ImageResult<image::RgbaImage>,
+    },
+    /// A file changed in the directory being watched.
+    FileChanged { path: PathBuf, change: FileChange },
+}
@@ -2,7 +2,17 @@ use ash::vk;
 
 pub struct Vertex {
     pub pos: glam::Vec2,
-    pub color: glam::Vec3,
+    pub uv: glam::Vec2,
+}
+
+/// Where one thumbnail of the gallery goes and which part of the atlas it
+/// shows, read once per instance of the unit quad.
+#[repr(C)]
+pub struct ThumbnailInstance {
+    /// Minimum and maximum corner in window pixels.
+    pub rect: glam::Vec4,
+    /// Minimum and maximum corner in atlas texture coordinates.
+    pub uv_rect: glam::Vec4,
 }
 
 impl Vertex {
@@ -23,12 +33,46 @@ impl Vertex {
             .binding(0)
             .format(vk::Format::R32G32_SFLOAT)
             .offset(std::mem::offset_of!(Vertex, pos).try_into()?);
-        let color_attribute = vk::VertexInputAttributeDescription::default()
+        let uv_attribute = vk::VertexInputAttributeDescription::default()
             .location(1)
             .binding(0)
-            .format(vk::Format::R32G32B32_SFLOAT)
-            .offset(std::mem::offset_of!(Vertex, color).try_into()?);
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(std::mem::offset_of!(Vertex, uv).try_into()?);
+
+        Ok([pos_attribute, uv_attribute])
+    }
+
+    /// Vertex binding followed by a per-instance binding of `ThumbnailInstance`.
+    pub fn get_instanced_binding_descriptions()
+    -> Result<[vk::VertexInputBindingDescription; 2], <u32 as TryFrom<usize>>::Error> {
+        let [vertex_bind_desc] = Self::get_binding_descriptions()?;
+        let instance_bind_desc = vk::VertexInputBindingDescription::default()
+            .binding(1)
+            .stride(size_of::<ThumbnailInstance>().try_into()?)
+            .input_rate(vk::VertexInputRate::INSTANCE);
+
+        Ok([vertex_bind_desc, instance_bind_desc])
+    }
+
+    pub fn get_instanced_attribute_descriptions()
+    -> Result<[vk::VertexInputAttributeDescription; 4], <u32 as TryFrom<usize>>::Error> {
+        let [pos_attribute, uv_attribute] = Self::get_attribute_descriptions()?;
+        let rect_attribute = vk::VertexInputAttributeDescription::default()
+            .location(2)
+            .binding(1)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(std::mem::offset_of!(ThumbnailInstance, rect).try_into()?);
+        let uv_rect_attribute = vk::VertexInputAttributeDescription::default()
+            .location(3)
+            .binding(1)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(std::mem::offset_of!(ThumbnailInstance, uv_rect).try_into()?);
 
-        Ok([pos_attribute, color_attribute])
+        Ok([
+            pos_attribute,
+            uv_attribute,
+            rect_attribute,
+            uv_rect_attribute,
+        ])
     }
 }
@@ -0,0 +1,133 @@
+use crate::user_event::UserEvent;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask, Watches};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+use winit::event_loop::EventLoopProxy;
+
+/// What happened to a file in the watched directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Modified,
+    Added,
+    Removed,
+    /// Moved within the directory, to the path given.
+    Renamed(PathBuf),
+}
+
+/// Watches one directory at a time with inotify, delivering changes to the
+/// files in it as `UserEvent::FileChanged`.
+pub struct Watcher {
+    watches: Watches,
+    /// The watched directory as the playlist spells it, so changed paths
+    /// compare equal to its entries.
+    watched: Arc<Mutex<Option<(WatchDescriptor, PathBuf)>>>,
+}
+
+impl Watcher {
+    pub fn new(proxy: EventLoopProxy<UserEvent>) -> io::Result<Self> {
+        let mut inotify = Inotify::init()?;
+        let watches = inotify.watches();
+        let watched = Arc::new(Mutex::new(None));
+
+        let shared = Arc::clone(&watched);
+        thread::Builder::new()
+            .name("watcher".to_owned())
+            .spawn(move || {
+                let mut buffer = [0; 4096];
+                loop {
+                    let Ok(events) = inotify.read_events_blocking(&mut buffer) else {
+                        return;
+                    };
+                    let events =
+                        events.map(|x| (x.wd, x.mask, x.cookie, x.name.map(PathBuf::from)));
+                    let changes = changes(&shared.lock().unwrap(), events);
+                    for (path, change) in changes {
+                        if proxy
+                            .send_event(UserEvent::FileChanged { path, change })
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self { watches, watched })
+    }
+
+    /// Moves the watch to `dir`, unless it is there already.
+    pub fn watch(&mut self, dir: &Path) {
+        let mut watched = self.watched.lock().unwrap();
+        if watched.as_ref().is_some_and(|(_, x)| x == dir) {
+            return;
+        }
+        if let Some((wd, _)) = watched.take() {
+            let _ = self.watches.remove(wd);
+        }
+
+        let mask = WatchMask::MODIFY
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO;
+        let target = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        match self.watches.add(target, mask) {
+            Ok(wd) => *watched = Some((wd, dir.to_path_buf())),
+            Err(e) => eprintln!("{}: failed to watch for changes: {e}", target.display()),
+        }
+    }
+}
+
+/// Turns one read's worth of inotify events into changes to files in the
+/// watched directory. A move out of the directory followed by a move back in
+/// with the same cookie is a rename.
+fn changes(
+    watched: &Option<(WatchDescriptor, PathBuf)>,
+    events: impl Iterator<Item = (WatchDescriptor, EventMask, u32, Option<PathBuf>)>,
+) -> Vec<(PathBuf, FileChange)> {
+    let Some((watched_wd, dir)) = watched else {
+        return Vec::new();
+    };
+
+    let mut changes: Vec<(PathBuf, FileChange)> = Vec::new();
+    let mut moved_from: Option<(u32, usize)> = None;
+    for (wd, mask, cookie, name) in events {
+        let Some(name) = name else {
+            continue;
+        };
+        if wd != *watched_wd || mask.contains(EventMask::ISDIR) {
+            continue;
+        }
+        let path = dir.join(name);
+
+        if mask.contains(EventMask::MOVED_TO)
+            && let Some((from_cookie, i)) = moved_from.take()
+            && from_cookie == cookie
+        {
+            changes[i].1 = FileChange::Renamed(path);
+            continue;
+        }
+
+        let change = if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+            FileChange::Added
+        } else if mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+            FileChange::Removed
+        } else {
+            FileChange::Modified
+        };
+        if mask.contains(EventMask::MOVED_FROM) {
+            moved_from = Some((cookie, changes.len()));
+        }
+        changes.push((path, change));
+    }
+    changes
+}
@@ -0,0 +1,93 @@
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::PathBuf};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+    window::{Window, WindowAttributes},
+};
+
+/// Windowed size and position in physical pixels, restored on the next run.
+/// Some platforms, Wayland among them, never report the position.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    size: [u32; 2],
+    position: Option<[i32; 2]>,
+}
+
+impl WindowGeometry {
+    pub fn of(window: &Window) -> Self {
+        let size = window.inner_size();
+        Self {
+            size: [size.width, size.height],
+            position: window.outer_position().ok().map(|x| [x.x, x.y]),
+        }
+    }
+
+    /// The geometry saved by the last run, if any.
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(Self::path()?).ok()?;
+        toml::from_str(&text).ok()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path().ok_or("no state directory")?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Restores the size, and the position moved onto one of `monitors` so a
+    /// monitor unplugged since doesn't leave the window out of sight.
+    pub fn apply(
+        &self,
+        attributes: WindowAttributes,
+        monitors: &[MonitorHandle],
+    ) -> WindowAttributes {
+        let [width, height] = self.size;
+        let attributes = attributes.with_inner_size(PhysicalSize::new(width, height));
+
+        match self.position {
+            Some(position) => {
+                let [x, y] = clamp_to_monitors(position, self.size, monitors);
+                attributes.with_position(PhysicalPosition::new(x, y))
+            }
+            None => attributes,
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        Some(paths::state_dir()?.join("window.toml"))
+    }
+}
+
+/// Moves a window of `size` at `position` onto the monitor nearest to its top
+/// left corner, keeping as much of it on that monitor as fits. Left as is
+/// when no monitors are reported.
+fn clamp_to_monitors(position: [i32; 2], size: [u32; 2], monitors: &[MonitorHandle]) -> [i32; 2] {
+    let [x, y] = position;
+    let bounds = monitors.iter().map(|monitor| {
+        let origin = monitor.position();
+        let extent = monitor.size();
+        (
+            [origin.x, origin.y],
+            [
+                origin.x + extent.width as i32,
+                origin.y + extent.height as i32,
+            ],
+        )
+    });
+    let distance = |([min_x, min_y], [max_x, max_y]): &([i32; 2], [i32; 2])| {
+        let dx = (min_x - x).max(x - max_x).max(0) as i64;
+        let dy = (min_y - y).max(y - max_y).max(0) as i64;
+        dx * dx + dy * dy
+    };
+    let Some(([min_x, min_y], [max_x, max_y])) = bounds.min_by_key(distance) else {
+        return position;
+    };
+
+    [
+        x.min(max_x - size[0] as i32).max(min_x),
+        y.min(max_y - size[1] as i32).max(min_y),
+    ]
+}